@@ -0,0 +1,70 @@
+//! Three PCA9634 boards, each on its own address and all in one sub-address group
+//!
+//! After a software reset every board is set up on its own address, then joins
+//! the group on sub-address 1. Single boards are driven through their own
+//! driver, the whole group with one write through `SubGroupWriter`.
+
+extern crate embedded_hal_bus;
+extern crate linux_embedded_hal;
+extern crate linux_embedded_hal_04 as hal;
+extern crate pca963x;
+
+use core::cell::RefCell;
+use embedded_hal_bus::i2c::RefCellDevice;
+use hal::I2cdev;
+use linux_embedded_hal::Delay;
+use pca963x::broadcast::DEFAULT_ALL_CALL_ADDRESS;
+use pca963x::{
+    part, software_reset, Address, BroadcastWriter, Channels8, Config, Eh1I2c, Error, LedOut,
+    SubGroupWriter, PCA9634, PCA963X,
+};
+
+/// Address pins of the boards, A0-A6
+const BOARDS: [u8; 3] = [0x20, 0x21, 0x22];
+
+/// Sub-address shared by all boards
+const GROUP: u8 = 0x68;
+
+fn main() -> Result<(), Error<hal::I2CError>> {
+    let bus = RefCell::new(I2cdev::new("/dev/i2c-1").unwrap());
+
+    // Back to the power-on state, whatever ran before
+    software_reset(&mut Eh1I2c::new(RefCellDevice::new(&bus)))?;
+
+    // All boards still answer on the all call address after the reset, set
+    // their outputs up in one go
+    let mut all = BroadcastWriter::<_, part::PCA9634>::new(
+        Eh1I2c::new(RefCellDevice::new(&bus)),
+        DEFAULT_ALL_CALL_ADDRESS,
+    );
+    all.write_all_out(LedOut::Pwm)?;
+
+    let mut delay = Delay;
+    let mut boards = [None, None, None];
+    for (board, address) in boards.iter_mut().zip(BOARDS) {
+        // Clears SLEEP and waits for the oscillator before any PWM is written
+        let i2c = Eh1I2c::new(RefCellDevice::new(&bus));
+        let mut pca = PCA9634::new_config_awake(
+            i2c,
+            Address::Custom(address),
+            Config::default(),
+            &mut delay,
+        )?;
+        pca.configure_subaddress(1, GROUP, true)?;
+        // Pick up the output modes broadcast above
+        pca.sync_cache()?;
+        *board = Some(pca);
+    }
+
+    // Per-board control, a different brightness on the first channel of each
+    for (i, pca) in boards.iter_mut().flatten().enumerate() {
+        pca.write_duty(Channels8::_1, 64 * (i as u8 + 1))?;
+    }
+
+    // Every board at once, the outputs change together
+    let mut group =
+        SubGroupWriter::<_, part::PCA9634>::new(Eh1I2c::new(RefCellDevice::new(&bus)), GROUP);
+    group.write_all_duty(&[255, 0, 128, 0, 255, 0, 128, 0])?;
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Two PCA9633 and a temperature sensor sharing one bus
+//!
+//! The drivers only borrow the bus for the duration of each transaction, so any
+//! embedded-hal 1.0 bus sharing proxy works. Here `RefCellDevice` from
+//! embedded-hal-bus wraps the bus and `Eh1I2c` adapts each proxy for the drivers.
+
+extern crate embedded_hal_1;
+extern crate embedded_hal_bus;
+extern crate linux_embedded_hal_04 as hal;
+extern crate pca963x;
+
+use core::cell::RefCell;
+use embedded_hal_1::i2c::I2c;
+use embedded_hal_bus::i2c::RefCellDevice;
+use hal::I2cdev;
+use pca963x::{AddressBuilder, Channels4, Config, Eh1I2c, Error, LedOut, PCA9633, PCA963X};
+
+/// TMP102 temperature sensor
+const TMP102: u8 = 0x48;
+
+fn main() -> Result<(), Error<hal::I2CError>> {
+    let bus = RefCell::new(I2cdev::new("/dev/i2c-1").unwrap());
+
+    let config = Config::default().sleep(false).all_call(false);
+    let mut left = PCA9633::new_config(
+        Eh1I2c::new(RefCellDevice::new(&bus)),
+        AddressBuilder::ten_pin().build(),
+        config,
+    )?;
+    let mut right = PCA9633::new_config(
+        Eh1I2c::new(RefCellDevice::new(&bus)),
+        AddressBuilder::ten_pin().a0(true).build(),
+        config,
+    )?;
+    let mut sensor = RefCellDevice::new(&bus);
+
+    left.write_all_out(LedOut::Pwm)?;
+    right.write_all_out(LedOut::Pwm)?;
+
+    // Show the temperature as a red-blue mix, 0°C blue to 40°C red
+    let mut raw = [0u8; 2];
+    sensor.write_read(TMP102, &[0x00], &mut raw)?;
+    let celsius = (i16::from_be_bytes(raw) >> 4) / 16;
+    let red = (celsius.clamp(0, 40) * 255 / 40) as u8;
+
+    for pca in [&mut left, &mut right] {
+        pca.write_duty(Channels4::_1, red)?;
+        pca.write_duty(Channels4::_3, 255 - red)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,326 @@
+//! Software driven effects
+//!
+//! The helpers in this module keep all of their state on the stack and are
+//! advanced by the caller, so the timing is entirely up to the application.
+
+use crate::{i2c, Channels, LedOut, PCA963X};
+
+/// Blink a channel a fixed number of times, then leave it off.
+///
+/// Hardware group blinking (`DmBlink`) runs continuously. `BlinkCount` instead
+/// toggles a channel between `LedOut::PwmGroup` and `LedOut::FullyOff` once per
+/// call to [`tick`](BlinkCount::tick), so a blink takes two ticks.
+#[derive(Copy, Clone, Debug)]
+pub struct BlinkCount<C> {
+    channel: C,
+    remaining: u16,
+}
+
+impl<C> BlinkCount<C>
+where
+    C: Channels + Copy,
+{
+    /// Blink `channel` `count` times
+    pub fn new(channel: C, count: u8) -> Self {
+        BlinkCount {
+            channel,
+            remaining: count as u16 * 2,
+        }
+    }
+
+    /// True once all blinks have been performed and the channel is off
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Advance one step, returns `false` once there is nothing left to do.
+    pub fn tick<D, I2C, E>(&mut self, pca: &mut D) -> Result<bool, E>
+    where
+        D: PCA963X<I2C, E, Channels = C>,
+        I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+    {
+        if self.is_done() {
+            return Ok(false);
+        }
+        let out = if self.remaining & 1 == 0 {
+            LedOut::PwmGroup
+        } else {
+            LedOut::FullyOff
+        };
+        pca.write_out(self.channel, out)?;
+        self.remaining -= 1;
+        Ok(!self.is_done())
+    }
+}
+
+/// Duty cycle of every channel of a device, channel 1 first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DutyFrame<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> DutyFrame<N> {
+    /// Frame with every channel set to `value`
+    pub const fn uniform(value: u8) -> Self {
+        DutyFrame([value; N])
+    }
+
+    /// Duty cycles as a slice
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Frame `t/255` of the way from this frame to `to`
+    pub fn crossfade(&self, to: &DutyFrame<N>, t: u8) -> DutyFrame<N> {
+        let mut frame = DutyFrame::default();
+        for (i, duty) in frame.0.iter_mut().enumerate() {
+            *duty = lerp(self.0[i], to.0[i], t as u16, 255);
+        }
+        frame
+    }
+}
+
+impl<const N: usize> Default for DutyFrame<N> {
+    fn default() -> Self {
+        DutyFrame::uniform(0)
+    }
+}
+
+/// Linear interpolation from `a` to `b` at `num / den`, rounded to the nearest
+/// value so that `num == den` lands exactly on `b`
+pub(crate) const fn lerp(a: u8, b: u8, num: u16, den: u16) -> u8 {
+    if den == 0 {
+        return b;
+    }
+    let diff = b as i32 - a as i32;
+    let sign = if diff < 0 { -1 } else { 1 };
+    let offs = (2 * diff * num as i32 + sign * den as i32) / (2 * den as i32);
+    (a as i32 + offs) as u8
+}
+
+/// Linear fade between two frames.
+///
+/// Yields `steps + 1` frames, starting with `from` and ending with `to`.
+#[derive(Copy, Clone, Debug)]
+pub struct Fade<const N: usize> {
+    from: DutyFrame<N>,
+    to: DutyFrame<N>,
+    steps: u16,
+    step: u16,
+    done: bool,
+}
+
+impl<const N: usize> Fade<N> {
+    /// Fade from `from` to `to` in `steps` steps
+    pub fn new(from: DutyFrame<N>, to: DutyFrame<N>, steps: u16) -> Self {
+        Fade {
+            from,
+            to,
+            steps,
+            step: 0,
+            done: false,
+        }
+    }
+}
+
+impl<const N: usize> Iterator for Fade<N> {
+    type Item = DutyFrame<N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut frame = DutyFrame::default();
+        for (i, duty) in frame.0.iter_mut().enumerate() {
+            *duty = lerp(self.from.0[i], self.to.0[i], self.step, self.steps);
+        }
+        if self.step >= self.steps {
+            self.done = true;
+        } else {
+            self.step += 1;
+        }
+        Some(frame)
+    }
+}
+
+/// Endless triangle wave on all channels between `min` and `max`.
+///
+/// Each ramp (up or down) takes `steps` frames.
+#[derive(Copy, Clone, Debug)]
+pub struct Breathe<const N: usize> {
+    min: u8,
+    max: u8,
+    steps: u16,
+    step: u16,
+    rising: bool,
+}
+
+impl<const N: usize> Breathe<N> {
+    /// Breathe between `min` and `max`, starting at `min`
+    pub fn new(min: u8, max: u8, steps: u16) -> Self {
+        Breathe {
+            min,
+            max,
+            steps: steps.max(1),
+            step: 0,
+            rising: true,
+        }
+    }
+}
+
+impl<const N: usize> Iterator for Breathe<N> {
+    type Item = DutyFrame<N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (from, to) = if self.rising {
+            (self.min, self.max)
+        } else {
+            (self.max, self.min)
+        };
+        let frame = DutyFrame::uniform(lerp(from, to, self.step, self.steps));
+        self.step += 1;
+        if self.step >= self.steps {
+            self.step = 0;
+            self.rising = !self.rising;
+        }
+        Some(frame)
+    }
+}
+
+/// Endless chase, a single lit channel moving from channel 1 upwards.
+#[derive(Copy, Clone, Debug)]
+pub struct Chase<const N: usize> {
+    on: u8,
+    off: u8,
+    position: usize,
+}
+
+impl<const N: usize> Chase<N> {
+    /// Chase with the lit channel at `on` and all others at `off`
+    pub fn new(on: u8, off: u8) -> Self {
+        Chase {
+            on,
+            off,
+            position: 0,
+        }
+    }
+}
+
+impl<const N: usize> Iterator for Chase<N> {
+    type Item = DutyFrame<N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if N == 0 {
+            return None;
+        }
+        let mut frame = DutyFrame::uniform(self.off);
+        frame.0[self.position] = self.on;
+        self.position = (self.position + 1) % N;
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod blink_tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{Address, Channels4, PCA9633};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+    use std::vec;
+
+    #[test]
+    fn test_blink_count() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x08, 0b0000_0011]),
+            Transaction::write(0x62, vec![0x08, 0b0000_0000]),
+            Transaction::write(0x62, vec![0x08, 0b0000_0011]),
+            Transaction::write(0x62, vec![0x08, 0b0000_0000]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+
+        let mut blink = BlinkCount::new(Channels4::_1, 2);
+        assert!(blink.tick(&mut pca).unwrap());
+        assert!(blink.tick(&mut pca).unwrap());
+        assert!(blink.tick(&mut pca).unwrap());
+        assert!(!blink.tick(&mut pca).unwrap());
+        assert!(blink.is_done());
+
+        // Settled, no more bus traffic
+        assert!(!blink.tick(&mut pca).unwrap());
+        i2c.done();
+    }
+}
+
+#[cfg(test)]
+mod engine_tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(lerp(0, 255, 1, 2), 128);
+        assert_eq!(lerp(10, 0, 1, 3), 7);
+        assert_eq!(lerp(10, 0, 3, 3), 0);
+        assert_eq!(lerp(0, 1, 1, 4), 0);
+        assert_eq!(lerp(0, 1, 2, 4), 1);
+        assert_eq!(lerp(0, 10, 0, 0), 10);
+        let down = |step| lerp(255, 0, step, 1000);
+        assert!((1..1000).all(|step| down(step + 1) <= down(step)));
+        assert_eq!(lerp(3, 250, 1000, 1000), 250);
+    }
+
+    #[test]
+    fn test_fade() {
+        let fade = Fade::new(DutyFrame([0, 255]), DutyFrame([100, 55]), 4);
+        let frames: Vec<_> = fade.collect();
+        assert_eq!(
+            frames,
+            [
+                DutyFrame([0, 255]),
+                DutyFrame([25, 205]),
+                DutyFrame([50, 155]),
+                DutyFrame([75, 105]),
+                DutyFrame([100, 55]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fade_no_steps() {
+        let frames: Vec<_> = Fade::new(DutyFrame([0]), DutyFrame([10]), 0).collect();
+        assert_eq!(frames, [DutyFrame([10])]);
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let from = DutyFrame([0, 100, 200, 50]);
+        let to = DutyFrame([100, 0, 50, 250]);
+        assert_eq!(from.crossfade(&to, 0), from);
+        assert_eq!(from.crossfade(&to, 128), DutyFrame([50, 50, 125, 150]));
+        assert_eq!(from.crossfade(&to, 255), to);
+    }
+
+    #[test]
+    fn test_breathe() {
+        let frames: Vec<DutyFrame<2>> = Breathe::new(0, 90, 3).take(7).collect();
+        let duties: Vec<_> = frames.iter().map(|f| f.0[0]).collect();
+        assert_eq!(duties, [0, 30, 60, 90, 60, 30, 0]);
+        assert!(frames.iter().all(|f| f.0[0] == f.0[1]));
+    }
+
+    #[test]
+    fn test_chase() {
+        let frames: Vec<DutyFrame<3>> = Chase::new(255, 0).step_by(2).take(3).collect();
+        assert_eq!(
+            frames,
+            [
+                DutyFrame([255, 0, 0]),
+                DutyFrame([0, 0, 255]),
+                DutyFrame([0, 255, 0]),
+            ]
+        );
+    }
+}
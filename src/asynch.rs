@@ -0,0 +1,315 @@
+//! Async drivers
+//!
+//! [`PCA963XAsync`] mirrors the blocking [`PCA963X`](crate::PCA963X) trait on
+//! top of the `embedded-hal-async` I2C trait, every bus access is awaited.
+//! Devices are created the same way, e.g. `PCA9633::new(i2c, Address::_8Pin)`.
+
+use crate::{
+    animation, pwm_register, Channels, Config, Error, LedOut, Mode1, Mode2, Och, OutDrv,
+    OutputDrive, OSC_SETTLE_US,
+};
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+
+#[allow(async_fn_in_trait)]
+pub trait PCA963XAsync<I2C, E>
+where
+    I2C: I2c<Error = E>,
+{
+    const MODE1: u8;
+    const MODE2: u8;
+    const PWM0: u8;
+    const GRPPWM: u8;
+    const GRPFREQ: u8;
+    const LEDOUT1: u8;
+    const SUBADR1: u8;
+    const SUBADR2: u8;
+    const SUBADR3: u8;
+    const ALLCALLADR: u8;
+
+    /// Number of channels
+    const NUM_CHANNELS: u8;
+    /// Number of LEDOUT registers, starting at `LEDOUT1`
+    const NUM_LEDOUT: u8;
+
+    type Channels: Channels;
+
+    /// Read a register in a single write-read transaction
+    async fn read(&mut self, register: u8) -> Result<u8, E>;
+
+    async fn read_duty(&mut self, ch: Self::Channels) -> Result<u8, E> {
+        self.read(pwm_register(Self::PWM0, ch.get_offs(), Self::NUM_CHANNELS))
+            .await
+    }
+
+    /// Write a register
+    async fn write(&mut self, register: u8, value: u8) -> Result<(), E>;
+
+    /// Write config
+    async fn write_config(&mut self, conf: Config) -> Result<(), E> {
+        self.write_modes_raw(conf.mode1.bits, conf.mode2.bits).await
+    }
+
+    /// Read config
+    async fn read_config(&mut self) -> Result<Config, E> {
+        let (mode1, mode2) = self.read_modes_raw().await?;
+        Ok(Config::from_raw(mode1, mode2))
+    }
+
+    /// Read MODE1 and MODE2 as is in one transaction
+    async fn read_modes_raw(&mut self) -> Result<(u8, u8), E>;
+
+    /// Write MODE1 and MODE2 as is in one transaction
+    ///
+    /// *Note: Unchecked, reserved bits are written too. Prefer `write_config`*
+    async fn write_modes_raw(&mut self, mode1: u8, mode2: u8) -> Result<(), E>;
+
+    /// Enter sleep mode, only the SLEEP bit of MODE1 is changed
+    async fn sleep(&mut self) -> Result<(), E> {
+        self.update_mode1(|mode1| mode1 | Mode1::Sleep).await
+    }
+
+    /// Leave sleep mode, only the SLEEP bit of MODE1 is changed
+    ///
+    /// *Note: The oscillator needs up to 500µs to start, PWM written before that
+    /// may glitch. See `wake_with_delay`*
+    async fn wake(&mut self) -> Result<(), E> {
+        self.update_mode1(|mode1| mode1 - Mode1::Sleep).await
+    }
+
+    /// Leave sleep mode and wait for the oscillator to start
+    async fn wake_with_delay<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), E> {
+        self.wake().await?;
+        delay.delay_us(OSC_SETTLE_US as u32).await;
+        Ok(())
+    }
+
+    /// Read MODE1, pass it through `f` and write the result back, see
+    /// [`PCA963X::update_mode1`](crate::PCA963X::update_mode1)
+    async fn update_mode1<F: FnOnce(Mode1) -> Mode1>(&mut self, f: F) -> Result<(), E> {
+        let mode1 = Mode1::from_bits_truncate(self.read(Self::MODE1).await?);
+        self.write(Self::MODE1, f(mode1).bits).await
+    }
+
+    /// Read MODE2, pass it through `f` and write the result back
+    async fn update_mode2<F: FnOnce(Mode2) -> Mode2>(&mut self, f: F) -> Result<(), E> {
+        let mode2 = Mode2::from_bits_truncate(self.read(Self::MODE2).await?);
+        self.write(Self::MODE2, f(mode2).bits).await
+    }
+
+    /// Set the INVERT flag, only MODE2 is changed
+    async fn set_invert(&mut self, enable: bool) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).invert(enable).mode2)
+            .await
+    }
+
+    /// Set when outputs are updated, only MODE2 is changed
+    async fn set_och(&mut self, change: Och) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).och(change).mode2)
+            .await
+    }
+
+    /// Set the output driver structure, only MODE2 is changed
+    async fn set_out_drv(&mut self, outdrv: OutDrv) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).out_drv(outdrv).mode2)
+            .await
+    }
+
+    /// Set the output idle behaviour, only MODE2 is changed
+    async fn set_outne(&mut self, out: OutputDrive) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).outne(out).mode2)
+            .await
+    }
+
+    /// Write channel pwm
+    async fn write_duty(&mut self, ch: Self::Channels, value: u8) -> Result<(), E> {
+        let register = pwm_register(Self::PWM0, ch.get_offs(), Self::NUM_CHANNELS);
+        self.write(register, value).await
+    }
+
+    /// Read the pwm of all channels in one transaction into `values`, which must
+    /// hold exactly `NUM_CHANNELS` values.
+    async fn read_all_duty(&mut self, values: &mut [u8]) -> Result<(), Error<E>>;
+
+    /// Write the pwm of all channels in one transaction, `values` must contain
+    /// exactly one value per channel.
+    async fn write_all_duty(&mut self, values: &[u8]) -> Result<(), Error<E>>;
+
+    /// Write channel pwm from a 16-bit value
+    ///
+    /// *Note: The PWM registers are 8-bit, the lower 8 bits of `value` are discarded*
+    async fn write_duty16(&mut self, ch: Self::Channels, value: u16) -> Result<(), E> {
+        self.write_duty(ch, (value >> 8) as u8).await
+    }
+
+    /// Fade channel pwm linearly from `from` to `to` in `steps` steps, see
+    /// [`PCA963X::fade_duty`](crate::PCA963X::fade_duty)
+    async fn fade_duty<D: DelayNs>(
+        &mut self,
+        ch: Self::Channels,
+        from: u8,
+        to: u8,
+        steps: u16,
+        step_delay_us: u32,
+        delay: &mut D,
+    ) -> Result<(), E>
+    where
+        Self::Channels: Copy,
+    {
+        if from == to {
+            return Ok(());
+        }
+        if steps == 0 {
+            return self.write_duty(ch, to).await;
+        }
+        self.write_duty(ch, from).await?;
+        for step in 1..=steps {
+            delay.delay_us(step_delay_us).await;
+            self.write_duty(ch, animation::lerp(from, to, step, steps))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Write channel output mode
+    async fn write_out(&mut self, ch: Self::Channels, out: LedOut) -> Result<(), E> {
+        let (index, shift) = ch.ledout_location();
+        let mut ledout = self.read(Self::LEDOUT1 + index).await?;
+        ledout &= !(0x03 << shift);
+        ledout |= (out as u8) << shift;
+        self.write(Self::LEDOUT1 + index, ledout).await
+    }
+
+    /// Write channel output mode to all outputs
+    async fn write_all_out(&mut self, out: LedOut) -> Result<(), E>;
+
+    /// Write the output mode of every channel in one transaction, `modes` must
+    /// contain exactly one mode per channel.
+    async fn write_all_out_array(&mut self, modes: &[LedOut]) -> Result<(), Error<E>>;
+
+    /// Write group duty cycle
+    async fn write_group_duty(&mut self, value: u8) -> Result<(), E> {
+        self.write(Self::GRPPWM, value).await
+    }
+    /// Write group frequency. Not used if `DmBlink` flag is not set in config.
+    async fn write_group_freq(&mut self, value: u8) -> Result<(), E> {
+        self.write(Self::GRPFREQ, value).await
+    }
+
+    /// Write sub address 1. Requires `Sub1` flag in config to be set.
+    async fn write_sub_address1(&mut self, addr: u8) -> Result<(), E> {
+        self.write(Self::SUBADR1, addr << 1).await
+    }
+
+    /// Write sub address 2. Requires `Sub2` flag in config to be set.
+    async fn write_sub_address2(&mut self, addr: u8) -> Result<(), E> {
+        self.write(Self::SUBADR2, addr << 1).await
+    }
+
+    /// Write sub address 3. Requires `Sub3` flag in config to be set.
+    async fn write_sub_address3(&mut self, addr: u8) -> Result<(), E> {
+        self.write(Self::SUBADR3, addr << 1).await
+    }
+
+    /// Write all call address. Requires `AllCall` flag in config to be set.
+    async fn write_all_call_address1(&mut self, addr: u8) -> Result<(), E> {
+        self.write(Self::ALLCALLADR, addr << 1).await
+    }
+}
+
+#[cfg(test)]
+mod async_tests {
+    use super::*;
+    use crate::mock::{block_on, RegisterFile};
+    use crate::{Address, Channels4, Channels8, PCA9633, PCA9634};
+
+    #[test]
+    fn test_async_duty() {
+        let mut pca = PCA9633::new(RegisterFile::new(0x62), Address::_8Pin);
+        block_on(pca.write_duty(Channels4::_2, 0x42)).unwrap();
+        assert_eq!(block_on(pca.read_duty(Channels4::_2)).unwrap(), 0x42);
+        assert_eq!(pca.i2c.regs[0x03], 0x42);
+    }
+
+    #[test]
+    fn test_async_config() {
+        let mut pca = PCA9634::new(RegisterFile::new(0x15), Address::Custom(0x15));
+        let config = Config::new().sleep(false).blink(true);
+        block_on(pca.write_config(config)).unwrap();
+        let read = block_on(pca.read_config()).unwrap();
+        assert_eq!((read.mode1, read.mode2), (config.mode1, config.mode2));
+        block_on(pca.sleep()).unwrap();
+        assert_eq!(pca.i2c.regs[0x00], config.mode1.bits | Mode1::Sleep.bits);
+    }
+
+    #[test]
+    fn test_async_all_out() {
+        let mut pca = PCA9634::new(RegisterFile::new(0x15), Address::Custom(0x15));
+        block_on(pca.write_all_out(LedOut::PwmGroup)).unwrap();
+        block_on(pca.write_out(Channels8::_5, LedOut::FullyOff)).unwrap();
+        assert_eq!(pca.i2c.regs[0x0C..0x0E], [0xFF, 0xFC]);
+        let mut modes = [LedOut::Pwm; 8];
+        modes[0] = LedOut::FullyOn;
+        block_on(pca.write_all_out_array(&modes)).unwrap();
+        assert_eq!(pca.i2c.regs[0x0C..0x0E], [0xA9, 0xAA]);
+        assert_eq!(
+            block_on(pca.write_all_out_array(&modes[..7])),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_async_all_duty() {
+        let mut pca = PCA9633::new(RegisterFile::new(0x62), Address::_8Pin);
+        block_on(pca.write_all_duty(&[1, 2, 3, 4])).unwrap();
+        let mut duties = [0u8; 4];
+        block_on(pca.read_all_duty(&mut duties)).unwrap();
+        assert_eq!(duties, [1, 2, 3, 4]);
+        assert_eq!(
+            block_on(pca.write_all_duty(&[1, 2])),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_async_mirror() {
+        let mut pca = PCA9633::new(RegisterFile::new(0x62), Address::_8Pin);
+        pca.mirror(Channels4::_2, &[Channels4::_3, Channels4::_4]);
+        block_on(pca.write_duty(Channels4::_2, 20)).unwrap();
+        assert_eq!(pca.i2c.regs[0x02..0x06], [0, 20, 20, 20]);
+        assert_eq!(pca.i2c.transactions, 1);
+        // A gap in the span is read back first, then written in one go
+        pca.mirror(Channels4::_1, &[Channels4::_4]);
+        block_on(pca.write_duty(Channels4::_1, 77)).unwrap();
+        assert_eq!(pca.i2c.regs[0x02..0x06], [77, 20, 20, 77]);
+        assert_eq!(pca.i2c.transactions, 3);
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_async_trace_bulk() {
+        let mut pca = PCA9634::new(RegisterFile::new(0x15), Address::Custom(0x15));
+        let traced = crate::mock::traced();
+
+        block_on(pca.write_modes_raw(0x01, 0x05)).unwrap();
+        block_on(pca.write_config(Config::new())).unwrap();
+        block_on(pca.read_config()).unwrap();
+        block_on(pca.write_all_duty(&[1; 8])).unwrap();
+        block_on(pca.read_all_duty(&mut [0; 8])).unwrap();
+        block_on(pca.write_all_out(LedOut::Pwm)).unwrap();
+        block_on(pca.write_all_out_array(&[LedOut::Pwm; 8])).unwrap();
+        pca.mirror(Channels8::_1, &[Channels8::_4]);
+        block_on(pca.write_duty(Channels8::_1, 7)).unwrap();
+
+        assert_eq!(crate::mock::traced() - traced, pca.i2c.transactions);
+    }
+
+    #[test]
+    fn test_async_mode2() {
+        let mut pca = PCA9633::new(RegisterFile::new(0x62), Address::_8Pin);
+        pca.i2c.regs[0x00..0x02].copy_from_slice(&[0x81, 0x05]);
+        block_on(pca.set_invert(true)).unwrap();
+        block_on(pca.set_out_drv(OutDrv::OpenDrain)).unwrap();
+        assert_eq!(pca.i2c.regs[0x00..0x02], [0x81, 0x11]);
+    }
+}
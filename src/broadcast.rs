@@ -0,0 +1,231 @@
+//! Writing to every device on the bus at once
+//!
+//! Devices with the `AllCall` flag set in MODE1 (the default) also answer on the
+//! all call address, 0x70 unless changed with `write_all_call_address1`. A write
+//! to it reaches all of them in a single transaction, e.g. to drive a rig of
+//! identical boards in lockstep.
+//!
+//! ```ignore
+//! let mut all = BroadcastWriter::<_, part::PCA9633>::new(i2c, DEFAULT_ALL_CALL_ADDRESS);
+//! all.write_all_out(LedOut::Pwm)?;
+//! all.write_all_duty(&[255, 128, 0, 0])?;
+//! ```
+//!
+//! Reads are not supported, every device would drive its response onto the bus
+//! at the same time.
+//!
+//! Sub-addresses reach a smaller, named group the same way, see
+//! [`SubGroupWriter`]. Each member needs the sub-address programmed and its
+//! `Sub1`/`Sub2`/`Sub3` flag set in MODE1, both done by `configure_subaddress`:
+//!
+//! ```ignore
+//! left.configure_subaddress(1, 0x71, true)?;
+//! right.configure_subaddress(1, 0x71, true)?;
+//! let mut group = SubGroupWriter::<_, part::PCA9633>::new(i2c, 0x71);
+//! group.write_all_duty(&[255, 128, 0, 0])?;
+//! ```
+
+use crate::part::{self, Part};
+use crate::{i2c, Channels, Error, LedOut, AUTOINCR_ALL, MAX_CHANNELS, MAX_LEDOUT};
+use core::marker::PhantomData;
+
+/// Power-on all call address
+pub const DEFAULT_ALL_CALL_ADDRESS: u8 = 0x70;
+
+/// Power-on sub addresses 1-3, only answered once the matching MODE1 flag is set
+pub const DEFAULT_SUB_ADDRESSES: [u8; 3] = [0x71, 0x72, 0x74];
+
+/// Write-only driver for all devices answering on a broadcast address
+///
+/// `M` selects the register layout, one of the [`part`] markers. All devices
+/// on the address must share that layout.
+///
+/// *Note: Nothing is read back, so `write_out` keeps the output modes it last
+/// broadcast, starting from the power-on state of all outputs off. Mirroring
+/// and inversion set up on the individual drivers are not applied.*
+#[derive(Debug)]
+pub struct BroadcastWriter<I2C, M = part::PCA9633> {
+    i2c: I2C,
+    address: u8,
+    ledouts: [u8; MAX_LEDOUT],
+    _part: PhantomData<M>,
+}
+
+impl<I2C, M: Part> BroadcastWriter<I2C, M> {
+    /// Broadcast on `address`, usually [`DEFAULT_ALL_CALL_ADDRESS`] or a sub-address
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        BroadcastWriter {
+            i2c,
+            address,
+            ledouts: [0; MAX_LEDOUT],
+            _part: PhantomData,
+        }
+    }
+
+    /// Return the bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Write channel pwm on every device
+    pub fn write_duty<E>(&mut self, ch: M::Channels, value: u8) -> Result<(), E>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        self.i2c
+            .write(self.address, &[M::PWM0 + ch.get_offs(), value])
+    }
+
+    /// Write the pwm of all channels on every device in one transaction, `values`
+    /// must contain exactly one value per channel.
+    pub fn write_all_duty<E>(&mut self, values: &[u8]) -> Result<(), Error<E>>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        if values.len() != M::Channels::ALL.len() {
+            return Err(Error::InvalidLength);
+        }
+        let mut buf = [0u8; MAX_CHANNELS + 1];
+        buf[0] = AUTOINCR_ALL | M::PWM0;
+        buf[1..values.len() + 1].copy_from_slice(values);
+        Ok(self.i2c.write(self.address, &buf[..values.len() + 1])?)
+    }
+
+    /// Write channel output mode on every device
+    pub fn write_out<E>(&mut self, ch: M::Channels, out: LedOut) -> Result<(), E>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        let (index, shift) = ch.ledout_location();
+        let ledout = (self.ledouts[index as usize] & !(0x03 << shift)) | (out as u8) << shift;
+        self.i2c
+            .write(self.address, &[M::LEDOUT1 + index, ledout])?;
+        self.ledouts[index as usize] = ledout;
+        Ok(())
+    }
+
+    /// Write channel output mode to all outputs of every device
+    pub fn write_all_out<E>(&mut self, out: LedOut) -> Result<(), E>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        let count = M::Channels::ALL.len().div_ceil(4);
+        let mut buf = [(out as u8) * 0b0101_0101; MAX_LEDOUT + 1];
+        buf[0] = AUTOINCR_ALL | M::LEDOUT1;
+        self.i2c.write(self.address, &buf[..count + 1])?;
+        self.ledouts = [buf[1]; MAX_LEDOUT];
+        Ok(())
+    }
+}
+
+/// Write-only driver for a group of devices sharing a sub-address
+///
+/// Works like [`BroadcastWriter`], but only the devices with the sub-address
+/// programmed and its `Sub1`, `Sub2` or `Sub3` flag set in MODE1 answer. Those
+/// flags are cleared at power-on.
+#[derive(Debug)]
+pub struct SubGroupWriter<I2C, M = part::PCA9633>(BroadcastWriter<I2C, M>);
+
+impl<I2C, M: Part> SubGroupWriter<I2C, M> {
+    /// Write to the group on sub-address `address`
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        SubGroupWriter(BroadcastWriter::new(i2c, address))
+    }
+
+    /// Return the bus
+    pub fn release(self) -> I2C {
+        self.0.release()
+    }
+
+    /// Write channel pwm on every device of the group
+    pub fn write_duty<E>(&mut self, ch: M::Channels, value: u8) -> Result<(), E>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        self.0.write_duty(ch, value)
+    }
+
+    /// Write the pwm of all channels on every device of the group in one
+    /// transaction, `values` must contain exactly one value per channel.
+    pub fn write_all_duty<E>(&mut self, values: &[u8]) -> Result<(), Error<E>>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        self.0.write_all_duty(values)
+    }
+
+    /// Write channel output mode on every device of the group
+    pub fn write_out<E>(&mut self, ch: M::Channels, out: LedOut) -> Result<(), E>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        self.0.write_out(ch, out)
+    }
+
+    /// Write channel output mode to all outputs of every device of the group
+    pub fn write_all_out<E>(&mut self, out: LedOut) -> Result<(), E>
+    where
+        I2C: i2c::Write<Error = E>,
+    {
+        self.0.write_all_out(out)
+    }
+}
+
+#[cfg(test)]
+mod broadcast_tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{Channels16, Channels4, Channels8};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+    use std::vec;
+
+    #[test]
+    fn test_broadcast_pca9633() {
+        let expectations = [
+            Transaction::write(0x70, vec![0x03, 0x42]),
+            Transaction::write(0x70, vec![0x82, 1, 2, 3, 4]),
+            Transaction::write(0x70, vec![0x88, 0xAA]),
+            Transaction::write(0x70, vec![0x08, 0xA8]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut all =
+            BroadcastWriter::<_, part::PCA9633>::new(i2c.clone(), DEFAULT_ALL_CALL_ADDRESS);
+        all.write_duty(Channels4::_2, 0x42).unwrap();
+        all.write_all_duty(&[1, 2, 3, 4]).unwrap();
+        assert!(matches!(
+            all.write_all_duty(&[1, 2]),
+            Err(Error::InvalidLength)
+        ));
+        all.write_all_out(LedOut::Pwm).unwrap();
+        all.write_out(Channels4::_1, LedOut::FullyOff).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_broadcast_pca9635() {
+        let expectations = [
+            Transaction::write(0x71, vec![0x94, 0x55, 0x55, 0x55, 0x55]),
+            Transaction::write(0x71, vec![0x17, 0x5D]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut all = BroadcastWriter::<_, part::PCA9635>::new(i2c.clone(), 0x71);
+        all.write_all_out(LedOut::FullyOn).unwrap();
+        all.write_out(Channels16::_14, LedOut::PwmGroup).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_sub_group() {
+        let expectations = [
+            Transaction::write(0x72, vec![0x82, 1, 2, 3, 4, 5, 6, 7, 8]),
+            Transaction::write(0x72, vec![0x0D, 0x02]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut group =
+            SubGroupWriter::<_, part::PCA9634>::new(i2c.clone(), DEFAULT_SUB_ADDRESSES[1]);
+        group.write_all_duty(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        group.write_out(Channels8::_5, LedOut::Pwm).unwrap();
+        i2c.done();
+    }
+}
@@ -0,0 +1,30 @@
+//! Single channel handles
+//!
+//! [`PcaChannel`] borrows a driver and one of its channels, so that the channel
+//! can be handed to code expecting a single PWM output. Created with
+//! `channel(ch)` on any of the devices.
+//!
+//! With the `embedded-hal-1` feature it implements the embedded-hal 1.0
+//! `SetDutyCycle` trait, with a maximum duty cycle of 255 (the PWM register
+//! range). Duty cycles are passed through as is, not scaled, and values above
+//! 255 saturate to fully on instead of wrapping. Unlike the whole-device `Pwm`
+//! impl of the `embedded-hal-pwm` feature, which has no way to report them and
+//! drops bus errors, every error is returned as an [`Error`](crate::Error).
+//!
+//! With the `embedded-hal-pwm` feature it implements the embedded-hal 0.2
+//! `PwmPin` trait too, best effort like `Pwm`. `enable` writes the device's
+//! `enable_mode`.
+
+/// One channel of a borrowed driver
+#[derive(Debug)]
+pub struct PcaChannel<'a, D> {
+    pub(crate) pca: &'a mut D,
+    pub(crate) offs: u8,
+}
+
+impl<'a, D> PcaChannel<'a, D> {
+    /// Offset of the channel, counting from 0
+    pub fn offset(&self) -> u8 {
+        self.offs
+    }
+}
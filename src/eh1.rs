@@ -0,0 +1,116 @@
+//! embedded-hal 1.0 support
+//!
+//! The drivers are written against the embedded-hal 0.2 blocking I2C traits.
+//! Wrap a bus implementing the 1.0 [`I2c`] trait in [`Eh1I2c`] to use it with
+//! any of the devices, bus errors are reported as the bus' own error type.
+//!
+//! ```ignore
+//! let mut pca9633 = PCA9633::new(Eh1I2c::new(i2c), Address::_8Pin);
+//! ```
+//!
+//! Bus sharing proxies such as embedded-hal-bus' `RefCellDevice` or
+//! `CriticalSectionDevice` implement `I2c` too, give each device its own proxy to
+//! share one bus between several drivers. See `examples/shared_bus.rs`.
+//!
+//! Single channels implement the 1.0 `SetDutyCycle` trait, see
+//! [`PcaChannel`](crate::PcaChannel).
+
+use crate::Error;
+use core::fmt::Debug;
+use embedded_hal_1::i2c::I2c;
+use embedded_hal_1::pwm;
+use hal::blocking::i2c;
+
+/// embedded-hal 1.0 I2C bus usable by the drivers
+#[derive(Debug)]
+pub struct Eh1I2c<I2C>(I2C);
+
+impl<I2C> Eh1I2c<I2C> {
+    /// Wrap `i2c`
+    pub fn new(i2c: I2C) -> Self {
+        Eh1I2c(i2c)
+    }
+
+    /// Return the wrapped bus
+    pub fn release(self) -> I2C {
+        self.0
+    }
+}
+
+impl<I2C: I2c> i2c::Write for Eh1I2c<I2C> {
+    type Error = I2C::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        I2c::write(&mut self.0, address, bytes)
+    }
+}
+
+impl<I2C: I2c> i2c::Read for Eh1I2c<I2C> {
+    type Error = I2C::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        I2c::read(&mut self.0, address, buffer)
+    }
+}
+
+impl<I2C: I2c> i2c::WriteRead for Eh1I2c<I2C> {
+    type Error = I2C::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        I2c::write_read(&mut self.0, address, bytes, buffer)
+    }
+}
+
+impl<E: Debug> pwm::Error for Error<E> {
+    fn kind(&self) -> pwm::ErrorKind {
+        pwm::ErrorKind::Other
+    }
+}
+
+#[cfg(test)]
+mod eh1_tests {
+    use super::*;
+    use crate::mock::RegisterFile;
+    use crate::{Address, Channels4, PCA9633, PCA963X};
+    use embedded_hal_1::i2c::{ErrorKind, NoAcknowledgeSource};
+    use embedded_hal_1::pwm::SetDutyCycle;
+
+    #[test]
+    fn test_eh1_duty() {
+        let mut pca = PCA9633::new(Eh1I2c::new(RegisterFile::new(0x62)), Address::_8Pin);
+        pca.write_duty(Channels4::_2, 0x42).unwrap();
+        assert_eq!(pca.read_duty(Channels4::_2).unwrap(), 0x42);
+    }
+
+    #[test]
+    fn test_eh1_error() {
+        let i2c = Eh1I2c::new(RegisterFile::new(0x62));
+        let mut pca = PCA9633::new(i2c, Address::Custom(0x10));
+        assert_eq!(
+            pca.write_duty(Channels4::_2, 0x42),
+            Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+        );
+    }
+
+    #[test]
+    fn test_set_duty_cycle() {
+        let mut pca = PCA9633::new(Eh1I2c::new(RegisterFile::new(0x62)), Address::_8Pin);
+        let mut ch = pca.channel(Channels4::_3);
+        assert_eq!(ch.max_duty_cycle(), 255);
+        ch.set_duty_cycle(255).unwrap();
+        assert_eq!(ch.duty(), 255);
+        ch.set_duty_cycle(300).unwrap();
+        assert_eq!(ch.duty(), 255);
+        ch.set_duty_cycle(u16::MAX).unwrap();
+        assert_eq!(ch.duty(), 255);
+        ch.set_duty_cycle(200).unwrap();
+        assert_eq!(ch.duty(), 200);
+        ch.set_duty_cycle_percent(50).unwrap();
+        assert_eq!(pca.read_duty(Channels4::_3).unwrap(), 127);
+    }
+}
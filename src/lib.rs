@@ -1,23 +1,136 @@
+//! Drivers for the PCA9633, PCA9634, PCA9635 and PCA9624 I2C LED controllers
+//!
+//! The devices power up asleep with all outputs off. Bringing one up takes four
+//! steps: clear SLEEP, give the oscillator 500µs to start, select an output mode
+//! per channel and write the duty cycles.
+//!
+//! ```
+//! use embedded_hal::blocking::delay::DelayUs;
+//! use embedded_hal::blocking::i2c;
+//! use pca963x::{Address, Channels4, Config, LedOut, PCA9633, PCA963X};
+//!
+//! fn start<I2C, E, D>(i2c: I2C, delay: &mut D) -> Result<PCA9633<I2C>, E>
+//! where
+//!     I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+//!     D: DelayUs<u16>,
+//! {
+//!     let mut pca = PCA9633::new(i2c, Address::_8Pin);
+//!     pca.write_config(Config::run())?;
+//!     delay.delay_us(500);
+//!     pca.write_all_out(LedOut::Pwm)?;
+//!     pca.write_duty(Channels4::_1, 128)?;
+//!     Ok(pca)
+//! }
+//! ```
+//!
+//! `new_config_awake` does the first three lines in one call.
+//!
+//! With the `trace` feature every transaction of a device is logged through
+//! `defmt` at trace level, single register and auto-increment transfers alike,
+//! with the device address, the register pointer, the name of the register it
+//! starts at and the bytes. Without it the logging compiles to nothing.
+
 #![no_std]
 #![allow(non_upper_case_globals)]
 #![allow(unused_imports)]
 
 extern crate bitflags;
 extern crate embedded_hal as hal;
+#[cfg(feature = "embedded-hal-1")]
+extern crate embedded_hal_1;
 
 use bitflags::bitflags;
+use core::convert::TryFrom;
+use core::fmt;
+use core::iter::Copied;
+use core::marker::PhantomData;
+#[cfg(feature = "drop-safe")]
+use core::mem::ManuallyDrop;
+use core::ops::RangeInclusive;
+use core::slice;
+#[cfg(feature = "async")]
+use embedded_hal_async::i2c::I2c as _;
+use hal::blocking::delay::DelayUs;
 use hal::blocking::i2c;
+use hal::digital::v2::OutputPin;
 
 #[cfg(feature = "embedded-hal-pwm")]
 use hal::Pwm;
 
-#[derive(Copy, Clone, Debug)]
+pub mod animation;
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod broadcast;
+pub mod channel;
+#[cfg(feature = "embedded-hal-1")]
+pub mod eh1;
+#[cfg(test)]
+mod mock;
+pub mod part;
+pub mod rgb;
+
+pub use animation::DutyFrame;
+#[cfg(feature = "async")]
+pub use asynch::PCA963XAsync;
+pub use broadcast::{BroadcastWriter, SubGroupWriter};
+pub use channel::PcaChannel;
+#[cfg(feature = "embedded-hal-1")]
+pub use eh1::Eh1I2c;
+use part::Part;
+pub use rgb::RgbLed;
+
+/// Driver errors
+///
+/// Register level methods (`read`, `write`, `write_duty`, ...) can only fail on
+/// the bus and return the bus error `E` as is. Methods that also check their
+/// arguments return `Error<E>`, with bus errors wrapped in `Error::I2c` through
+/// the `From<E>` impl, so `?` works on both.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// I2C bus error
+    I2c(E),
+    /// Registers read back don't match what was written
+    VerificationFailed,
+    /// Slice length doesn't match the number of channels
+    InvalidLength,
+    /// Config can't be used with this device, see [`PCA963X::supports_config`]
+    UnsupportedConfig,
+    /// Not a usable 7-bit address, see [`Address::custom_checked`]
+    InvalidAddress,
+    /// Value or index outside of what the device supports
+    OutOfRange,
+    /// Channel output mode is neither `Pwm` nor `PwmGroup`, so its PWM value has
+    /// no visible effect, see [`PCA963X::write_out`]
+    NotPwm(LedOut),
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::I2c(e)
+    }
+}
+
+/// Device address, from the package pins or a custom value
+///
+/// Equality compares variants, `_8Pin` is not equal to `Custom(0x62)`. Compare
+/// [`Address::address`] for the bus address.
+///
+/// Prefer [`AddressBuilder`] over spelling out every pin of `_10Pin` and `_16Pin`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Address {
     /// 8 pin package, fixed address of 0x62
+    ///
+    /// There are no address pins but the sub-addresses and all call address
+    /// can still be programmed and enabled.
     _8Pin,
-    /// 10 pin package with A0 and A1 pins
+    /// 10 pin package with A0 and A1 pins, fixed base of 0x60 (`0b110_00xx`)
     _10Pin { a0: bool, a1: bool },
     /// 16 pin package with A0-A6 pins
+    ///
+    /// All seven address bits come from the pins, there is no fixed base.
     _16Pin {
         a0: bool,
         a1: bool,
@@ -28,11 +141,34 @@ pub enum Address {
         a6: bool,
     },
     /// Custom address determined programmatically
+    ///
+    /// Not validated, prefer [`Address::custom_checked`].
     Custom(u8),
 }
 
+/// Rejected custom address, see [`Address::custom_checked`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AddressError {
+    /// Doesn't fit in 7 bits, likely an 8-bit address including the R/W bit
+    NotSevenBit(u8),
+    /// Reserved by the I2C specification (0x00-0x07 and 0x78-0x7F)
+    Reserved(u8),
+}
+
 impl Address {
-    pub fn address(self) -> u8 {
+    /// Custom 7-bit address, rejecting values of 0x80 and above and the addresses
+    /// reserved by the I2C specification
+    pub fn custom_checked(addr: u8) -> Result<Address, AddressError> {
+        match addr {
+            0x80..=0xFF => Err(AddressError::NotSevenBit(addr)),
+            0x00..=0x07 | 0x78..=0x7F => Err(AddressError::Reserved(addr)),
+            _ => Ok(Address::Custom(addr)),
+        }
+    }
+
+    /// 7-bit bus address, usable in const context
+    pub const fn address(self) -> u8 {
         match self {
             Address::_8Pin => 0x62u8,
             Address::_10Pin { a0, a1 } => 0x60 | (a0 as u8) | (a1 as u8) << 1,
@@ -56,6 +192,147 @@ impl Address {
             Address::Custom(addr) => addr,
         }
     }
+
+    /// All addresses a device at this address answers to with `config` written
+    /// and `subs` programmed into its sub-address registers.
+    pub fn responding_addresses(
+        self,
+        config: &Config,
+        subs: &SubAddresses,
+    ) -> impl Iterator<Item = u8> {
+        let enabled = |flag: Mode1, addr: u8| config.mode1.contains(flag).then_some(addr);
+        IntoIterator::into_iter([
+            Some(self.address()),
+            enabled(Mode1::Sub1, subs.sub1),
+            enabled(Mode1::Sub2, subs.sub2),
+            enabled(Mode1::Sub3, subs.sub3),
+            enabled(Mode1::AllCall, subs.all_call),
+        ])
+        .flatten()
+    }
+}
+
+/// Programmed sub-addresses and all call address (7-bit)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SubAddresses {
+    pub sub1: u8,
+    pub sub2: u8,
+    pub sub3: u8,
+    pub all_call: u8,
+}
+
+impl Default for SubAddresses {
+    /// Power-on values
+    fn default() -> Self {
+        SubAddresses {
+            sub1: 0x71,
+            sub2: 0x72,
+            sub3: 0x74,
+            all_call: 0x70,
+        }
+    }
+}
+
+/// Named address pin setters for the 10 and 16 pin packages, unset pins are low
+///
+/// ```
+/// # use pca963x::{Address, AddressBuilder};
+/// let address = AddressBuilder::sixteen_pin().a0(true).a3(true).build();
+/// assert_eq!(address.address(), 0x09);
+/// ```
+///
+/// The 10 pin package only has A0 and A1, `a2`-`a6` are ignored for it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AddressBuilder {
+    pins: u8,
+    sixteen_pin: bool,
+}
+
+impl AddressBuilder {
+    /// 10 pin package with A0 and A1 pins, see [`Address::_10Pin`]
+    pub const fn ten_pin() -> Self {
+        AddressBuilder {
+            pins: 0,
+            sixteen_pin: false,
+        }
+    }
+
+    /// 16 pin package with A0-A6 pins, see [`Address::_16Pin`]
+    pub const fn sixteen_pin() -> Self {
+        AddressBuilder {
+            pins: 0,
+            sixteen_pin: true,
+        }
+    }
+
+    const fn pin(mut self, n: u8, high: bool) -> Self {
+        if high {
+            self.pins |= 1 << n;
+        } else {
+            self.pins &= !(1 << n);
+        }
+        self
+    }
+
+    /// Set pin A0
+    pub const fn a0(self, high: bool) -> Self {
+        self.pin(0, high)
+    }
+
+    /// Set pin A1
+    pub const fn a1(self, high: bool) -> Self {
+        self.pin(1, high)
+    }
+
+    /// Set pin A2, 16 pin package only
+    pub const fn a2(self, high: bool) -> Self {
+        self.pin(2, high)
+    }
+
+    /// Set pin A3, 16 pin package only
+    pub const fn a3(self, high: bool) -> Self {
+        self.pin(3, high)
+    }
+
+    /// Set pin A4, 16 pin package only
+    pub const fn a4(self, high: bool) -> Self {
+        self.pin(4, high)
+    }
+
+    /// Set pin A5, 16 pin package only
+    pub const fn a5(self, high: bool) -> Self {
+        self.pin(5, high)
+    }
+
+    /// Set pin A6, 16 pin package only
+    pub const fn a6(self, high: bool) -> Self {
+        self.pin(6, high)
+    }
+
+    const fn high(self, n: u8) -> bool {
+        self.pins & (1 << n) != 0
+    }
+
+    /// The address with the pins set so far
+    pub const fn build(self) -> Address {
+        if self.sixteen_pin {
+            Address::_16Pin {
+                a0: self.high(0),
+                a1: self.high(1),
+                a2: self.high(2),
+                a3: self.high(3),
+                a4: self.high(4),
+                a5: self.high(5),
+                a6: self.high(6),
+            }
+        } else {
+            Address::_10Pin {
+                a0: self.high(0),
+                a1: self.high(1),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -67,6 +344,18 @@ mod address_tests {
         assert_eq!(Address::_8Pin.address(), 0x62);
     }
 
+    #[test]
+    fn test_const_address() {
+        const LEFT: u8 = Address::_10Pin {
+            a0: true,
+            a1: false,
+        }
+        .address();
+        const TABLE: [u8; 2] = [Address::_8Pin.address(), Address::Custom(0x15).address()];
+        const _: () = assert!(LEFT == 0x61);
+        assert_eq!(TABLE, [0x62, 0x15]);
+    }
+
     #[test]
     fn test_10pin_address() {
         assert_eq!(Address::_10Pin{a0: false, a1: false}.address(), 0x60);
@@ -76,13 +365,86 @@ mod address_tests {
 
     }
 
+    #[test]
+    fn test_address_builder() {
+        let ten_pin = AddressBuilder::ten_pin().a1(true);
+        let expected = Address::_10Pin {
+            a0: false,
+            a1: true,
+        };
+        assert_eq!(ten_pin.build(), expected);
+        assert_eq!(ten_pin.a6(true).build().address(), 0x62);
+        const SIXTEEN_PIN: Address = AddressBuilder::sixteen_pin().a0(true).a6(true).build();
+        assert_eq!(SIXTEEN_PIN.address(), 0x41);
+        let cleared = AddressBuilder::sixteen_pin().a2(true).a2(false).build();
+        assert_eq!(cleared.address(), 0x00);
+    }
+
+    #[test]
+    fn test_16pin_address() {
+        let pins = |bits: u8| Address::_16Pin {
+            a0: bits & 0x01 != 0,
+            a1: bits & 0x02 != 0,
+            a2: bits & 0x04 != 0,
+            a3: bits & 0x08 != 0,
+            a4: bits & 0x10 != 0,
+            a5: bits & 0x20 != 0,
+            a6: bits & 0x40 != 0,
+        };
+        assert_eq!(pins(0b000_0001).address(), 0x01);
+        assert_eq!(pins(0b110_0010).address(), 0x62);
+        assert_eq!(pins(0b101_0101).address(), 0x55);
+        assert_eq!(pins(0b111_0111).address(), 0x77);
+    }
+
     #[test]
     fn test_custom_address() {
         assert_eq!(Address::Custom(0x1B).address(), 0x1B);
     }
+
+    #[test]
+    fn test_custom_checked() {
+        let checked = |addr| Address::custom_checked(addr).map(Address::address);
+        assert_eq!(checked(0x00), Err(AddressError::Reserved(0x00)));
+        assert_eq!(checked(0x07), Err(AddressError::Reserved(0x07)));
+        assert_eq!(checked(0x08), Ok(0x08));
+        assert_eq!(checked(0x77), Ok(0x77));
+        assert_eq!(checked(0x78), Err(AddressError::Reserved(0x78)));
+        assert_eq!(checked(0x7F), Err(AddressError::Reserved(0x7F)));
+        assert_eq!(checked(0x80), Err(AddressError::NotSevenBit(0x80)));
+        assert_eq!(checked(0xC4), Err(AddressError::NotSevenBit(0xC4)));
+    }
+
+    #[test]
+    fn test_8pin_responding_addresses() {
+        let config = Config::new().all_call(false).sub1(true);
+        let subs = SubAddresses {
+            sub1: 0x44,
+            ..Default::default()
+        };
+        let mut addrs = Address::_8Pin.responding_addresses(&config, &subs);
+        assert_eq!(addrs.next(), Some(0x62));
+        assert_eq!(addrs.next(), Some(0x44));
+        assert_eq!(addrs.next(), None);
+    }
+
+    #[test]
+    fn test_default_responding_addresses() {
+        let config = Config::new();
+        let subs = SubAddresses::default();
+        let mut addrs = Address::_8Pin.responding_addresses(&config, &subs);
+        assert_eq!(addrs.next(), Some(0x62));
+        assert_eq!(addrs.next(), Some(0x70));
+        assert_eq!(addrs.next(), None);
+    }
 }
 
 bitflags! {
+    /// MODE1 flags
+    ///
+    /// Bits 7-5 are the auto-increment flags, set per transfer by the drivers.
+    /// None of the supported parts has an EXTCLK bit (the PCA9685's bit 6), they
+    /// always run from the internal oscillator.
     pub struct Mode1: u8 {
         const Sleep     = 0b0001_0000;
         const Sub1      = 0b0000_1000;
@@ -103,25 +465,281 @@ bitflags! {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Log a transaction of a device with the `trace` feature: the register
+/// pointer, the register it starts at and the bytes, e.g.
+/// `write 0x62 0x82 Some(Pwm(0)) = [0x80, 0x40]`
+///
+/// Only used in the bus helpers of [`Pca963x`], `Self` is the device type.
+/// Without the feature this expands to nothing, its arguments aren't evaluated.
+#[cfg(feature = "trace")]
+macro_rules! trace_register {
+    ($op:literal, $address:expr, $pointer:expr, $data:expr) => {{
+        #[cfg(test)]
+        crate::mock::count_traced();
+        defmt::trace!(
+            "{=str} {=u8:#04x} {=u8:#04x} {} = {=[u8]:#04x}",
+            $op,
+            $address,
+            $pointer,
+            Self::register_name($pointer & !AUTOINCR_MASK),
+            $data
+        )
+    }};
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_register {
+    ($($arg:tt)*) => {};
+}
+
+/// Raw bits followed by the names of the set flags
+#[cfg(feature = "defmt")]
+macro_rules! format_flags {
+    ($flags:ty, $($flag:ident),*) => {
+        impl defmt::Format for $flags {
+            fn format(&self, f: defmt::Formatter) {
+                defmt::write!(f, "{=str}({=u8:#010b}", stringify!($flags), self.bits);
+                $(
+                    if self.contains(<$flags>::$flag) {
+                        defmt::write!(f, " {=str}", stringify!($flag));
+                    }
+                )*
+                defmt::write!(f, ")");
+            }
+        }
+    };
+}
+
+#[cfg(feature = "defmt")]
+format_flags!(Mode1, Sleep, Sub1, Sub2, Sub3, AllCall);
+#[cfg(feature = "defmt")]
+format_flags!(Mode2, DmBlink, Invert, Och, OutDrv, OutNe1, OutNe0);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum LedOut {
     /// LED is fully off
-    FullyOff,
+    FullyOff = 0,
     /// LED id fully on
-    FullyOn,
+    FullyOn = 1,
     /// LED brightness is controlled through its PWMx
-    Pwm,
+    Pwm = 2,
     /// LED brightness is controlled through its PWMx and group duty/blinking.
-    PwmGroup,
+    PwmGroup = 3,
+}
+
+// Written as `out as u8` into the LDRx fields, pin the datasheet encoding
+const _: () = assert!(
+    LedOut::FullyOff as u8 == 0b00
+        && LedOut::FullyOn as u8 == 0b01
+        && LedOut::Pwm as u8 == 0b10
+        && LedOut::PwmGroup as u8 == 0b11
+);
+
+impl LedOut {
+    /// Decode the two lowest bits of `bits`
+    fn from_bits(bits: u8) -> LedOut {
+        match bits & 0x03 {
+            0 => LedOut::FullyOff,
+            1 => LedOut::FullyOn,
+            2 => LedOut::Pwm,
+            _ => LedOut::PwmGroup,
+        }
+    }
+}
+
+/// LEDOUT register of the channel at `offs`, counting from `LEDOUT1`, and the
+/// shift of its 2-bit field in that register
+pub const fn ledout_location(offs: u8) -> (u8, u8) {
+    (offs / 4, (offs % 4) * 2)
+}
+
+/// Pack channel output modes into `N` LEDOUT register values, four channels per byte.
+///
+/// Channels without a mode are `FullyOff`, modes beyond `4 * N` are ignored.
+pub fn pack_ledout<const N: usize>(modes: &[LedOut]) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    for (i, out) in modes.iter().take(4 * N).enumerate() {
+        let (index, shift) = ledout_location(i as u8);
+        bytes[index as usize] |= (*out as u8) << shift;
+    }
+    bytes
+}
+
+/// Unpack the output modes of `N` channels from LEDOUT register values.
+///
+/// Channels not covered by `bytes` are `FullyOff`.
+pub fn unpack_ledout<const N: usize>(bytes: &[u8]) -> [LedOut; N] {
+    let mut modes = [LedOut::FullyOff; N];
+    for (i, out) in modes.iter_mut().enumerate() {
+        let (index, shift) = ledout_location(i as u8);
+        if let Some(byte) = bytes.get(index as usize) {
+            *out = LedOut::from_bits(byte >> shift);
+        }
+    }
+    modes
+}
+
+#[cfg(test)]
+mod ledout_tests {
+    use super::*;
+
+    #[test]
+    fn test_ledout_location() {
+        let expected = [(0, 0), (0, 2), (0, 4), (0, 6)];
+        for (ch, location) in Channels4::all().zip(expected) {
+            assert_eq!(ch.ledout_location(), location);
+        }
+        let expected = [
+            (0, 0),
+            (0, 2),
+            (0, 4),
+            (0, 6),
+            (1, 0),
+            (1, 2),
+            (1, 4),
+            (1, 6),
+        ];
+        for (ch, location) in Channels8::all().zip(expected) {
+            assert_eq!(ch.ledout_location(), location);
+        }
+        let expected = [
+            (0, 0),
+            (0, 2),
+            (0, 4),
+            (0, 6),
+            (1, 0),
+            (1, 2),
+            (1, 4),
+            (1, 6),
+            (2, 0),
+            (2, 2),
+            (2, 4),
+            (2, 6),
+            (3, 0),
+            (3, 2),
+            (3, 4),
+            (3, 6),
+        ];
+        for (ch, location) in Channels16::all().zip(expected) {
+            assert_eq!(ch.ledout_location(), location);
+        }
+        assert_eq!(Channels16::all().count(), expected.len());
+    }
+
+    #[test]
+    fn test_pack_ledout() {
+        let modes = [
+            LedOut::FullyOff,
+            LedOut::FullyOn,
+            LedOut::Pwm,
+            LedOut::PwmGroup,
+            LedOut::PwmGroup,
+            LedOut::Pwm,
+        ];
+        let bytes: [u8; 2] = pack_ledout(&modes);
+        assert_eq!(bytes, [0b1110_0100, 0b0000_1011]);
+    }
+
+    #[test]
+    fn test_ledout_round_trip() {
+        let modes = [
+            LedOut::Pwm,
+            LedOut::FullyOn,
+            LedOut::PwmGroup,
+            LedOut::FullyOff,
+            LedOut::FullyOn,
+            LedOut::PwmGroup,
+            LedOut::Pwm,
+            LedOut::FullyOn,
+        ];
+        let bytes: [u8; 2] = pack_ledout(&modes);
+        let unpacked: [LedOut; 8] = unpack_ledout(&bytes);
+        for (a, b) in modes.iter().zip(unpacked.iter()) {
+            assert_eq!(*a as u8, *b as u8);
+        }
+    }
+
+    #[test]
+    fn test_unpack_short() {
+        let modes: [LedOut; 8] = unpack_ledout(&[0xFF]);
+        assert_eq!(modes[3] as u8, LedOut::PwmGroup as u8);
+        assert_eq!(modes[4] as u8, LedOut::FullyOff as u8);
+    }
+}
+
+/// Device independent register names, resolved with [`PCA963X::register`]
+///
+/// Indices follow the datasheet, `Pwm` and `LedOut` count from 0 and `SubAddr`
+/// from 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Register {
+    Mode1,
+    Mode2,
+    /// PWMn, duty cycle of channel n + 1
+    Pwm(u8),
+    GrpPwm,
+    GrpFreq,
+    /// LEDOUTn, output modes of channels 4n + 1 to 4n + 4
+    LedOut(u8),
+    /// SUBADRn, n from 1 to 3
+    SubAddr(u8),
+    AllCallAddr,
+}
+
+/// Auto-increment mode of the control register, see [`PCA963X::write_registers`]
+///
+/// Once the last register of the selected span is reached the register pointer
+/// rolls over to the first one of the span.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AutoIncrement {
+    /// No auto-increment, every byte goes to the same register
+    None = 0b0000_0000,
+    /// All registers
+    All = 0b1000_0000,
+    /// Individual brightness registers (PWMx) only
+    Brightness = 0b1010_0000,
+    /// Global control registers (GRPPWM and GRPFREQ) only
+    Global = 0b1100_0000,
+    /// Individual brightness and global control registers
+    BrightnessGlobal = 0b1110_0000,
 }
 
 /// Internal trait
-pub trait Channels {
+pub trait Channels: Sized + 'static {
+    /// Every channel, ordered by offset
+    const ALL: &'static [Self];
+
     fn get_offs(self) -> u8;
+
+    /// Channel with offset `index`, counting from 0, `None` if out of range
+    fn from_index(index: u8) -> Option<Self>;
+
+    /// LEDOUT register index and field shift of the channel, see [`ledout_location`]
+    fn ledout_location(self) -> (u8, u8) {
+        crate::ledout_location(self.get_offs())
+    }
+
+    /// Iterate over every channel, ordered by offset
+    fn all() -> Copied<slice::Iter<'static, Self>>
+    where
+        Self: Copy,
+    {
+        Self::ALL.iter().copied()
+    }
 }
 
+/// Channel index out of range for the channel type, returned by `TryFrom<u8>`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelOutOfRange(pub u8);
+
 /// 4 channels
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Channels4 {
     /// Channel 1
     _1 = 0,
@@ -134,13 +752,28 @@ pub enum Channels4 {
 }
 
 impl Channels for Channels4 {
+    const ALL: &'static [Self] = &[Channels4::_1, Channels4::_2, Channels4::_3, Channels4::_4];
+
     fn get_offs(self) -> u8 {
         self as u8
     }
+
+    fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+impl TryFrom<u8> for Channels4 {
+    type Error = ChannelOutOfRange;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or(ChannelOutOfRange(index))
+    }
 }
 
 /// 8 channels
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Channels8 {
     /// Channel 1
     _1 = 0,
@@ -161,13 +794,147 @@ pub enum Channels8 {
 }
 
 impl Channels for Channels8 {
+    const ALL: &'static [Self] = &[
+        Channels8::_1,
+        Channels8::_2,
+        Channels8::_3,
+        Channels8::_4,
+        Channels8::_5,
+        Channels8::_6,
+        Channels8::_7,
+        Channels8::_8,
+    ];
+
+    fn get_offs(self) -> u8 {
+        self as u8
+    }
+
+    fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+impl TryFrom<u8> for Channels8 {
+    type Error = ChannelOutOfRange;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or(ChannelOutOfRange(index))
+    }
+}
+
+/// 16 channels
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Channels16 {
+    /// Channel 1
+    _1 = 0,
+    /// Channel 2
+    _2 = 1,
+    /// Channel 3
+    _3 = 2,
+    /// Channel 4
+    _4 = 3,
+    /// Channel 5
+    _5 = 4,
+    /// Channel 6
+    _6 = 5,
+    /// Channel 7
+    _7 = 6,
+    /// Channel 8
+    _8 = 7,
+    /// Channel 9
+    _9 = 8,
+    /// Channel 10
+    _10 = 9,
+    /// Channel 11
+    _11 = 10,
+    /// Channel 12
+    _12 = 11,
+    /// Channel 13
+    _13 = 12,
+    /// Channel 14
+    _14 = 13,
+    /// Channel 15
+    _15 = 14,
+    /// Channel 16
+    _16 = 15,
+}
+
+impl Channels for Channels16 {
+    const ALL: &'static [Self] = &[
+        Channels16::_1,
+        Channels16::_2,
+        Channels16::_3,
+        Channels16::_4,
+        Channels16::_5,
+        Channels16::_6,
+        Channels16::_7,
+        Channels16::_8,
+        Channels16::_9,
+        Channels16::_10,
+        Channels16::_11,
+        Channels16::_12,
+        Channels16::_13,
+        Channels16::_14,
+        Channels16::_15,
+        Channels16::_16,
+    ];
+
     fn get_offs(self) -> u8 {
         self as u8
     }
+
+    fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+impl TryFrom<u8> for Channels16 {
+    type Error = ChannelOutOfRange;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or(ChannelOutOfRange(index))
+    }
+}
+
+#[cfg(test)]
+mod channel_tests {
+    use super::*;
+
+    #[test]
+    fn test_from_index() {
+        for i in 0..4 {
+            assert_eq!(Channels4::try_from(i).unwrap().get_offs(), i);
+        }
+        for i in 0..8 {
+            assert_eq!(Channels8::try_from(i).unwrap().get_offs(), i);
+        }
+        for i in 0..16 {
+            assert_eq!(Channels16::from_index(i).unwrap().get_offs(), i);
+        }
+        assert!(Channels16::from_index(16).is_none());
+    }
+
+    #[test]
+    fn test_all() {
+        assert_eq!(Channels4::ALL.len(), 4);
+        assert!(Channels8::all().map(Channels::get_offs).eq(0..8));
+        assert!(Channels16::all().map(Channels::get_offs).eq(0..16));
+    }
+
+    #[test]
+    fn test_from_index_out_of_range() {
+        assert_eq!(Channels4::try_from(4).unwrap_err(), ChannelOutOfRange(4));
+        assert_eq!(Channels8::try_from(8).unwrap_err(), ChannelOutOfRange(8));
+        assert_eq!(Channels16::try_from(16).unwrap_err(), ChannelOutOfRange(16));
+        assert_eq!(Channels4::try_from(99).unwrap_err(), ChannelOutOfRange(99));
+    }
 }
 
-/// Output drive mode
-#[derive(Copy, Clone, Debug)]
+/// Output drive mode, the discriminant is the value of the OUTNE[1:0] field
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OutputDrive {
     /// When OE = 1 (output drivers not enabled), LEDn = 0
     OutNe00 = 0x00,
@@ -179,31 +946,67 @@ pub enum OutputDrive {
     OutNe10 = 0x02,
 }
 
-/// Output change mode
-#[derive(Copy, Clone, Debug)]
+/// Output change mode, the discriminant is the value of the OCH bit
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Och {
     /// Outputs change on STOP command.
-    ChangeOnStop,
+    ChangeOnStop = 0,
     /// Outputs change on ACK.
-    ChangeOnAck,
+    ChangeOnAck = 1,
 }
 
-// Output change mode
-#[derive(Copy, Clone, Debug)]
+/// Output driver structure, the discriminant is the value of the OUTDRV bit
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OutDrv {
     /// The 4 LED outputs are configured with an open-drain structure.
-    OpenDrain,
+    OpenDrain = 0,
     /// The 4 LED outputs are configured with a totem pole structure.
-    TotemPole,
+    TotemPole = 1,
+}
+
+/// Function of the group control registers (DMBLNK)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GroupMode {
+    /// GRPPWM dims the `LedOut::PwmGroup` outputs, GRPFREQ is ignored
+    Dimming,
+    /// GRPFREQ and GRPPWM set the blink period and duty cycle
+    Blinking,
 }
 
 /// Driver configuration registers
-#[derive(Copy, Clone, Debug)]
+///
+/// `Debug` prints the decoded flags rather than the raw register values.
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "(u8, u8)", into = "(u8, u8)")
+)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Config {
     mode1: Mode1,
     mode2: Mode2,
 }
 
+/// Serialized as the raw MODE1 and MODE2 values
+impl From<Config> for (u8, u8) {
+    fn from(config: Config) -> Self {
+        config.raw()
+    }
+}
+
+impl From<(u8, u8)> for Config {
+    fn from((mode1, mode2): (u8, u8)) -> Self {
+        Config::from_raw(mode1, mode2)
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -213,6 +1016,44 @@ impl Default for Config {
     }
 }
 
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mode1, mode2) = (self.mode1, self.mode2);
+        let och = if mode2.contains(Mode2::Och) {
+            Och::ChangeOnAck
+        } else {
+            Och::ChangeOnStop
+        };
+        let out_drv = if mode2.contains(Mode2::OutDrv) {
+            OutDrv::TotemPole
+        } else {
+            OutDrv::OpenDrain
+        };
+        let outne = match mode2.bits & 0x03 {
+            0 => OutputDrive::OutNe00,
+            1 => OutputDrive::OutNe01,
+            _ => OutputDrive::OutNe10,
+        };
+        let group_mode = if mode2.contains(Mode2::DmBlink) {
+            GroupMode::Blinking
+        } else {
+            GroupMode::Dimming
+        };
+        f.debug_struct("Config")
+            .field("sleep", &mode1.contains(Mode1::Sleep))
+            .field("sub1", &mode1.contains(Mode1::Sub1))
+            .field("sub2", &mode1.contains(Mode1::Sub2))
+            .field("sub3", &mode1.contains(Mode1::Sub3))
+            .field("all_call", &mode1.contains(Mode1::AllCall))
+            .field("invert", &mode2.contains(Mode2::Invert))
+            .field("och", &och)
+            .field("out_drv", &out_drv)
+            .field("outne", &outne)
+            .field("group_mode", &group_mode)
+            .finish()
+    }
+}
+
 impl Config {
     /// Default configs but with sleep-mode disabled
     pub fn new() -> Self {
@@ -222,6 +1063,43 @@ impl Config {
         }
     }
 
+    /// Single device running on its own: sleep mode and all call address off,
+    /// totem pole outputs and group dimming
+    ///
+    /// Unlike `new` the device won't answer on the all call address, so other
+    /// masters broadcasting there can't change it.
+    pub fn run() -> Self {
+        Config {
+            mode1: Mode1::empty(),
+            mode2: Mode2::OutDrv | Mode2::OutNe0,
+        }
+    }
+
+    /// Config from raw MODE1 and MODE2 register values
+    ///
+    /// Read-only and reserved bits (e.g. the auto-increment flags in MODE1) are dropped.
+    pub fn from_raw(mode1: u8, mode2: u8) -> Self {
+        Config {
+            mode1: Mode1::from_bits_truncate(mode1),
+            mode2: Mode2::from_bits_truncate(mode2),
+        }
+    }
+
+    /// MODE1 register value
+    pub fn mode1_bits(&self) -> u8 {
+        self.mode1.bits
+    }
+
+    /// MODE2 register value
+    pub fn mode2_bits(&self) -> u8 {
+        self.mode2.bits
+    }
+
+    /// MODE1 and MODE2 register values, the inverse of [`Config::from_raw`]
+    pub fn raw(&self) -> (u8, u8) {
+        (self.mode1.bits, self.mode2.bits)
+    }
+
     /// Enable subaddress 1
     pub fn sub1(mut self, enable: bool) -> Config {
         self.mode1.set(Mode1::Sub1, enable);
@@ -240,7 +1118,8 @@ impl Config {
         self
     }
 
-    /// Enable all call address
+    /// Enable all call address (default on), the address itself is set with
+    /// `write_all_call_address1`
     pub fn all_call(mut self, enable: bool) -> Config {
         self.mode1.set(Mode1::AllCall, enable);
         self
@@ -258,6 +1137,14 @@ impl Config {
         self
     }
 
+    /// Select group dimming or blinking, same as `blink(false)` / `blink(true)`
+    pub fn group_mode(self, mode: GroupMode) -> Config {
+        match mode {
+            GroupMode::Dimming => self.blink(false),
+            GroupMode::Blinking => self.blink(true),
+        }
+    }
+
     /// Output logic inverted
     pub fn invert(mut self, enable: bool) -> Config {
         self.mode2.set(Mode2::Invert, enable);
@@ -266,37 +1153,35 @@ impl Config {
 
     /// Control when outputs are updated
     pub fn och(mut self, change: Och) -> Config {
-        match change {
-            Och::ChangeOnStop => self.mode2.set(Mode2::Och, false),
-            Och::ChangeOnAck => self.mode2.set(Mode2::Och, true),
-        }
+        self.mode2.remove(Mode2::Och);
+        let och = Mode2::from_bits_truncate((change as u8) << 3);
+        self.mode2.insert(och);
         self
     }
 
     /// Control output driver structure
     pub fn out_drv(mut self, outdrv: OutDrv) -> Config {
-        match outdrv {
-            OutDrv::OpenDrain => self.mode2.set(Mode2::OutDrv, false),
-            OutDrv::TotemPole => self.mode2.set(Mode2::OutDrv, true),
-        }
+        self.mode2.remove(Mode2::OutDrv);
+        let out_drv = Mode2::from_bits_truncate((outdrv as u8) << 2);
+        self.mode2.insert(out_drv);
         self
     }
 
     /// Control output idle behaviour
     pub fn outne(mut self, out: OutputDrive) -> Config {
-        match out {
-            OutputDrive::OutNe00 => self.mode2.remove(Mode2::OutNe1 | Mode2::OutNe0),
-            OutputDrive::OutNe01 => {
-                self.mode2.insert(Mode2::OutNe0);
-                self.mode2.remove(Mode2::OutNe1);
-            }
-            OutputDrive::OutNe10 => {
-                self.mode2.remove(Mode2::OutNe0);
-                self.mode2.insert(Mode2::OutNe1);
-            }
-        }
+        self.mode2.remove(Mode2::OutNe1 | Mode2::OutNe0);
+        self.mode2.insert(Mode2::from_bits_truncate(out as u8));
         self
     }
+
+    /// Recommended config for the type of `device`, never in sleep mode
+    pub fn with_defaults_for<D, I2C, E>(_device: &D) -> Config
+    where
+        D: PCA963X<I2C, E>,
+        I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+    {
+        D::recommended_config()
+    }
 }
 
 #[cfg(test)]
@@ -354,6 +1239,81 @@ mod test_config {
         assert_eq!(config.mode2.bits, 0b0000_0001); // Per 7.3.1
     }
 
+    #[test]
+    fn test_from_raw() {
+        let config = Config::from_raw(0b1001_0001, 0b1100_0101);
+        assert_eq!(config.mode1.bits, 0b0001_0001);
+        assert_eq!(config.mode2.bits, 0b0000_0101);
+    }
+
+    #[test]
+    fn test_bits() {
+        let config = Config::new().sleep(true).blink(true);
+        assert_eq!(config.mode1_bits(), 0b0001_0001);
+        assert_eq!(config.mode2_bits(), 0b0010_0101);
+        assert_eq!(config.raw(), (0b0001_0001, 0b0010_0101));
+        let (mode1, mode2) = config.raw();
+        assert_eq!(Config::from_raw(mode1, mode2).raw(), config.raw());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        let config = Config::new().sub2(true).blink(true).invert(true);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, "[5,53]");
+        let read: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.raw(), config.raw());
+
+        let address = Address::_10Pin {
+            a0: true,
+            a1: false,
+        };
+        let json = serde_json::to_string(&address).unwrap();
+        let read: Address = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.address(), address.address());
+    }
+
+    #[test]
+    fn test_run() {
+        let config = Config::run();
+        assert_eq!(config.mode1_bits(), 0x00);
+        assert_eq!(config.mode2_bits(), 0x05);
+        assert_eq!(config, Config::new().all_call(false).blink(false));
+    }
+
+    #[test]
+    fn test_debug() {
+        extern crate std;
+
+        let config = Config::default().sub2(true).blink(true);
+        assert_eq!(
+            std::format!("{:?}", config),
+            "Config { sleep: true, sub1: false, sub2: true, sub3: false, all_call: true, \
+             invert: false, och: ChangeOnStop, out_drv: TotemPole, outne: OutNe01, \
+             group_mode: Blinking }"
+        );
+    }
+
+    #[test]
+    fn test_eq() {
+        let config = Config::new().invert(true);
+        assert_eq!(config, Config::from_raw(0x01, 0x15));
+        assert_ne!(config, Config::new());
+        assert_ne!(Address::_8Pin, Address::Custom(0x62));
+        assert_eq!(Address::_8Pin.address(), Address::Custom(0x62).address());
+        assert_ne!(LedOut::Pwm, LedOut::PwmGroup);
+    }
+
+    #[test]
+    fn test_group_mode() {
+        let blinking = Config::new().group_mode(GroupMode::Blinking);
+        assert_eq!(blinking.raw(), Config::new().blink(true).raw());
+        assert!(blinking.mode2.contains(Mode2::DmBlink));
+        let dimming = blinking.group_mode(GroupMode::Dimming);
+        assert_eq!(dimming.raw(), Config::new().raw());
+    }
+
     #[test]
     fn test_outne() {
         let mut config: Config = Config::default().outne(OutputDrive::OutNe00);
@@ -363,17 +1323,204 @@ mod test_config {
         config = config.outne(OutputDrive::OutNe10);
         assert_eq!(config.mode2.bits, 0b0000_0110); // Per 7.3.1
     }
+
+    #[test]
+    fn test_mode2_encoding() {
+        let clear = Config::from_raw(0, 0);
+        assert_eq!(clear.och(Och::ChangeOnStop).mode2.bits, 0);
+        assert_eq!(clear.och(Och::ChangeOnAck).mode2, Mode2::Och);
+        assert_eq!(clear.out_drv(OutDrv::OpenDrain).mode2.bits, 0);
+        assert_eq!(clear.out_drv(OutDrv::TotemPole).mode2, Mode2::OutDrv);
+        assert_eq!(clear.outne(OutputDrive::OutNe00).mode2.bits, 0);
+        assert_eq!(clear.outne(OutputDrive::OutNe01).mode2, Mode2::OutNe0);
+        assert_eq!(clear.outne(OutputDrive::OutNe10).mode2, Mode2::OutNe1);
+        // Changing one field leaves the others alone
+        let set = Config::from_raw(0, 0xFF);
+        assert_eq!(set.och(Och::ChangeOnStop).mode2.bits, 0x37);
+        assert_eq!(set.out_drv(OutDrv::OpenDrain).mode2.bits, 0x3B);
+        assert_eq!(set.outne(OutputDrive::OutNe01).mode2.bits, 0x3D);
+    }
 }
 
 //const AUTOINCR_NONE: u8     = 0b0000_0000;
 const AUTOINCR_ALL: u8 = 0b1000_0000;
-//const AUTOINCR_BRIGHT: u8 = 0b1010_0000;
+const AUTOINCR_BRIGHT: u8 = 0b1010_0000;
 //const AUTOINCR_GLOBAL: u8 = 0b1100_0000;
 //const AUTOINCR_GLBR: u8 = 0b1110_0000;
+#[cfg(feature = "trace")]
+const AUTOINCR_MASK: u8 = 0b1110_0000;
 
-pub trait PCA963X<I2C, E>
+/// Longest span accepted by [`PCA963X::write_registers`]
+pub const MAX_REGISTER_SPAN: usize = 32;
+
+/// Number of LEDOUT registers needed for `channels` channels
+const fn ledout_count(channels: usize) -> usize {
+    channels.div_ceil(4)
+}
+
+/// Channels of the largest part, the caches and transfer buffers are sized for it
+const MAX_CHANNELS: usize = 16;
+
+/// LEDOUT registers of the largest part
+const MAX_LEDOUT: usize = ledout_count(MAX_CHANNELS);
+
+/// Number of registers of a device with `channels` channels, MODE1 to ALLCALLADR
+const fn register_count(channels: usize) -> usize {
+    channels + ledout_count(channels) + 8
+}
+
+/// Oscillator start-up time after clearing SLEEP
+const OSC_SETTLE_US: u16 = 500;
+
+/// Pending wake-up started with [`PCA963X::begin_wake`]
+///
+/// Pwm written before the oscillator is running may glitch. Timestamps are in
+/// microseconds of the same timer passed to `begin_wake` and may wrap around.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[must_use = "wait for the oscillator before using the outputs"]
+pub struct WakeToken {
+    ready_at_us: u32,
+}
+
+impl WakeToken {
+    /// Time from which the outputs may be used
+    pub fn ready_at(&self) -> u32 {
+        self.ready_at_us
+    }
+
+    /// True once `now_us` is at or past [`ready_at`](WakeToken::ready_at)
+    pub fn is_ready(&self, now_us: u32) -> bool {
+        (now_us.wrapping_sub(self.ready_at_us) as i32) >= 0
+    }
+}
+
+/// Driver that sets all outputs to a safe state when dropped, created with the
+/// devices' `with_drop_safe`
+///
+/// Dereferences to the driver. `into_inner` returns the driver without applying
+/// the state.
+///
+/// *Note: Errors during the final write are ignored*
+#[cfg(feature = "drop-safe")]
+pub struct DropSafe<D> {
+    pca: ManuallyDrop<D>,
+    state: LedOut,
+    apply: fn(&mut D, LedOut),
+}
+
+#[cfg(feature = "drop-safe")]
+impl<D> DropSafe<D> {
+    /// Return the driver, the safe state is not applied
+    pub fn into_inner(self) -> D {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so `pca` is taken exactly once
+        unsafe { ManuallyDrop::take(&mut this.pca) }
+    }
+}
+
+#[cfg(feature = "drop-safe")]
+impl<D> core::ops::Deref for DropSafe<D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        &self.pca
+    }
+}
+
+#[cfg(feature = "drop-safe")]
+impl<D> core::ops::DerefMut for DropSafe<D> {
+    fn deref_mut(&mut self) -> &mut D {
+        &mut self.pca
+    }
+}
+
+#[cfg(feature = "drop-safe")]
+impl<D> Drop for DropSafe<D> {
+    fn drop(&mut self) {
+        (self.apply)(&mut self.pca, self.state);
+        // SAFETY: `pca` is not used again after this
+        unsafe { ManuallyDrop::drop(&mut self.pca) }
+    }
+}
+
+/// Software reset call address
+const SWRST_ADDRESS: u8 = 0x03;
+
+/// Reset every PCA963x on the bus to its power-on state
+///
+/// Uses the SWRST call (address 0x03, data 0xA5 0x5A) rather than the general call
+/// reset (0x00, 0x06) which these parts don't implement.
+pub fn software_reset<I2C, E>(i2c: &mut I2C) -> Result<(), E>
+where
+    I2C: i2c::Write<Error = E>,
+{
+    i2c.write(SWRST_ADDRESS, &[0xA5, 0x5A])
+}
+
+/// Addresses in `range` that ACK a read of register 0x00 (MODE1)
+///
+/// For finding a device when the address pin wiring is uncertain. Other I2C
+/// devices answer too, an address showing up is not proof of a PCA963x there.
+/// Bus errors count as no device.
+///
+/// ```ignore
+/// for address in scan(&mut i2c, 0x60..=0x6F) {
+///     // ...
+/// }
+/// ```
+pub fn scan<I2C>(i2c: &mut I2C, range: RangeInclusive<u8>) -> impl Iterator<Item = u8> + '_
+where
+    I2C: i2c::WriteRead,
+{
+    range.filter(move |&address| i2c.write_read(address, &[0x00], &mut [0u8]).is_ok())
+}
+
+/// Bus without a combined write-read, for HALs that only implement `Write` and `Read`
+///
+/// The drivers read registers with `WriteRead`, a repeated START between
+/// writing the register pointer and reading. This wrapper instead does a
+/// separate write and read with a STOP in between, which the devices accept too.
+#[derive(Debug)]
+pub struct SplitWriteRead<I2C>(I2C);
+
+impl<I2C> SplitWriteRead<I2C> {
+    /// Wrap `i2c`
+    pub fn new(i2c: I2C) -> Self {
+        SplitWriteRead(i2c)
+    }
+
+    /// Return the wrapped bus
+    pub fn release(self) -> I2C {
+        self.0
+    }
+}
+
+impl<I2C, E> i2c::Write for SplitWriteRead<I2C>
+where
+    I2C: i2c::Write<Error = E>,
+{
+    type Error = E;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), E> {
+        self.0.write(address, bytes)
+    }
+}
+
+impl<I2C, E> i2c::WriteRead for SplitWriteRead<I2C>
 where
     I2C: i2c::Write<Error = E> + i2c::Read<Error = E>,
+{
+    type Error = E;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), E> {
+        self.0.write(address, bytes)?;
+        self.0.read(address, buffer)
+    }
+}
+
+pub trait PCA963X<I2C, E>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
 {
     const MODE1: u8;
     const MODE2: u8;
@@ -386,37 +1533,368 @@ where
     const SUBADR3: u8;
     const ALLCALLADR: u8;
 
+    /// Number of channels
+    const NUM_CHANNELS: u8;
+    /// Number of LEDOUT registers, starting at `LEDOUT1`
+    const NUM_LEDOUT: u8;
+
     type Channels: Channels;
 
+    /// Number of channels, `NUM_CHANNELS` for device-agnostic loops over
+    /// `0..pca.num_channels()`
+    fn num_channels(&self) -> u8 {
+        Self::NUM_CHANNELS
+    }
+
     /// Read a register
+    ///
+    /// Uses a single write-read transaction (repeated START), wrap buses without
+    /// `WriteRead` in [`SplitWriteRead`]. The register pointer is written without
+    /// auto-increment flags, so earlier auto-incremented transfers don't affect
+    /// which register is read.
     fn read(&mut self, register: u8) -> Result<u8, E>;
 
     fn read_duty(&mut self, ch: Self::Channels) -> Result<u8, E> {
-        self.read(Self::PWM0 + ch.get_offs())
+        self.read(pwm_register(Self::PWM0, ch.get_offs(), Self::NUM_CHANNELS))
     }
 
     /// Write a register
     fn write(&mut self, register: u8, value: u8) -> Result<(), E>;
 
+    /// Offset of `reg` on this device, `None` if the device doesn't have it
+    fn register(reg: Register) -> Option<u8>
+    where
+        Self: Sized,
+    {
+        match reg {
+            Register::Mode1 => Some(Self::MODE1),
+            Register::Mode2 => Some(Self::MODE2),
+            Register::Pwm(n) if n < Self::NUM_CHANNELS => Some(Self::PWM0 + n),
+            Register::GrpPwm => Some(Self::GRPPWM),
+            Register::GrpFreq => Some(Self::GRPFREQ),
+            Register::LedOut(n) if n < Self::NUM_LEDOUT => Some(Self::LEDOUT1 + n),
+            Register::SubAddr(1) => Some(Self::SUBADR1),
+            Register::SubAddr(2) => Some(Self::SUBADR2),
+            Register::SubAddr(3) => Some(Self::SUBADR3),
+            Register::AllCallAddr => Some(Self::ALLCALLADR),
+            _ => None,
+        }
+    }
+
+    /// Read a register by name, `Error::OutOfRange` if the device doesn't have it
+    fn read_reg(&mut self, reg: Register) -> Result<u8, Error<E>>
+    where
+        Self: Sized,
+    {
+        let register = Self::register(reg).ok_or(Error::OutOfRange)?;
+        Ok(self.read(register)?)
+    }
+
+    /// Write a register by name, `Error::OutOfRange` if the device doesn't have it
+    fn write_reg(&mut self, reg: Register, value: u8) -> Result<(), Error<E>>
+    where
+        Self: Sized,
+    {
+        let register = Self::register(reg).ok_or(Error::OutOfRange)?;
+        Ok(self.write(register, value)?)
+    }
+
+    /// Write `data` to the registers from `start` on in one transaction, the
+    /// register pointer advancing as given by `mode`
+    ///
+    /// At most `MAX_REGISTER_SPAN` bytes are written at once, `Error::InvalidLength`
+    /// otherwise. `Error::OutOfRange` if `start` is past the last register.
+    ///
+    /// *Note: Unchecked like `write_modes_raw`, inversion, mirroring and the cached
+    /// output modes are bypassed*
+    fn write_registers(
+        &mut self,
+        start: u8,
+        mode: AutoIncrement,
+        data: &[u8],
+    ) -> Result<(), Error<E>>;
+
+    /// Read the registers from `start` on into `buf` in one transaction, the
+    /// register pointer advancing as given by `mode`
+    ///
+    /// `Error::OutOfRange` if `start` is past the last register.
+    fn read_registers(
+        &mut self,
+        start: u8,
+        mode: AutoIncrement,
+        buf: &mut [u8],
+    ) -> Result<(), Error<E>>;
+
     /// Write config
-    fn write_config(&mut self, conf: Config) -> Result<(), E>;
+    fn write_config(&mut self, conf: Config) -> Result<(), E> {
+        self.write_modes_raw(conf.mode1.bits, conf.mode2.bits)
+    }
+
+    /// Read config
+    fn read_config(&mut self) -> Result<Config, E> {
+        let (mode1, mode2) = self.read_modes_raw()?;
+        Ok(Config::from_raw(mode1, mode2))
+    }
+
+    /// Read MODE1 and MODE2 as is in one transaction
+    fn read_modes_raw(&mut self) -> Result<(u8, u8), E>;
+
+    /// Check whether the device answers on the bus by reading MODE1
+    ///
+    /// embedded-hal 0.2 bus errors don't say why a transfer failed, so every bus
+    /// error, not just a missing acknowledge, is reported as absent.
+    fn probe(&mut self) -> bool {
+        self.read(Self::MODE1).is_ok()
+    }
+
+    /// True if the SLEEP bit of MODE1 is cleared, bus errors are propagated
+    fn is_awake(&mut self) -> Result<bool, E> {
+        let mode1 = self.read(Self::MODE1)?;
+        Ok(mode1 & Mode1::Sleep.bits == 0)
+    }
+
+    /// Enter sleep mode, only the SLEEP bit of MODE1 is changed
+    ///
+    /// The oscillator stops and the outputs are off, but every register keeps its
+    /// value. Unlike the PCA9685 there is no RESTART bit, leaving sleep resumes
+    /// the previous PWM, group and LEDOUT state on its own.
+    fn sleep(&mut self) -> Result<(), E> {
+        self.update_mode1(|mode1| mode1 | Mode1::Sleep)
+    }
+
+    /// Leave sleep mode, only the SLEEP bit of MODE1 is changed
+    ///
+    /// *Note: The oscillator needs up to 500µs to start, PWM written before that
+    /// may glitch. See `wake_with_delay`*
+    fn wake(&mut self) -> Result<(), E> {
+        self.update_mode1(|mode1| mode1 - Mode1::Sleep)
+    }
+
+    /// Leave sleep mode and wait for the oscillator to start
+    fn wake_with_delay<D: DelayUs<u16>>(&mut self, delay: &mut D) -> Result<(), E> {
+        self.wake()?;
+        delay.delay_us(OSC_SETTLE_US);
+        Ok(())
+    }
+
+    /// Leave sleep mode without waiting, `now_us` is the current time of a free
+    /// running microsecond timer
+    ///
+    /// The datasheet requires at least 500µs after clearing SLEEP before the PWM
+    /// outputs are used, the returned token tells when that has passed.
+    fn begin_wake(&mut self, now_us: u32) -> Result<WakeToken, E> {
+        self.wake()?;
+        Ok(WakeToken {
+            ready_at_us: now_us.wrapping_add(OSC_SETTLE_US as u32),
+        })
+    }
+
+    /// Read MODE1, pass it through `f` and write the result back, so several flags
+    /// change in one read and one write
+    ///
+    /// The auto-increment bits are read-only and always written as 0.
+    fn update_mode1<F: FnOnce(Mode1) -> Mode1>(&mut self, f: F) -> Result<(), E> {
+        let mode1 = Mode1::from_bits_truncate(self.read(Self::MODE1)?);
+        self.write(Self::MODE1, f(mode1).bits)
+    }
+
+    /// Read MODE2, pass it through `f` and write the result back, see `update_mode1`
+    fn update_mode2<F: FnOnce(Mode2) -> Mode2>(&mut self, f: F) -> Result<(), E> {
+        let mode2 = Mode2::from_bits_truncate(self.read(Self::MODE2)?);
+        self.write(Self::MODE2, f(mode2).bits)
+    }
+
+    /// Set the INVERT flag, only MODE2 is changed
+    fn set_invert(&mut self, enable: bool) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).invert(enable).mode2)
+    }
+
+    /// Set when outputs are updated, only MODE2 is changed
+    fn set_och(&mut self, change: Och) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).och(change).mode2)
+    }
+
+    /// Set the output driver structure, only MODE2 is changed
+    fn set_out_drv(&mut self, outdrv: OutDrv) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).out_drv(outdrv).mode2)
+    }
+
+    /// Set the output idle behaviour, only MODE2 is changed
+    fn set_outne(&mut self, out: OutputDrive) -> Result<(), E> {
+        self.update_mode2(|mode2| Config::from_raw(0, mode2.bits).outne(out).mode2)
+    }
+
+    /// Write MODE1 and MODE2 as is in one transaction
+    ///
+    /// *Note: Unchecked, reserved bits are written too. Prefer `write_config`*
+    fn write_modes_raw(&mut self, mode1: u8, mode2: u8) -> Result<(), E>;
+
+    /// Recommended config for this device, see [`Config::with_defaults_for`]
+    fn recommended_config() -> Config
+    where
+        Self: Sized;
+
+    /// Whether `conf` can be used with this device, true unless noted for the device
+    fn supports_config(_conf: &Config) -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    /// Write config, rejecting configs the device doesn't support with
+    /// `Error::UnsupportedConfig` before anything is written
+    fn write_config_checked(&mut self, conf: Config) -> Result<(), Error<E>>
+    where
+        Self: Sized,
+    {
+        if !Self::supports_config(&conf) {
+            return Err(Error::UnsupportedConfig);
+        }
+        Ok(self.write_config(conf)?)
+    }
 
     /// Write channel pwm
+    ///
+    /// Only visible while the channel is in the `Pwm` or `PwmGroup` output mode,
+    /// the devices' `write_duty_checked` reports the other two.
     fn write_duty(&mut self, ch: Self::Channels, value: u8) -> Result<(), E> {
-        self.write(Self::PWM0 + ch.get_offs(), value)
+        let register = pwm_register(Self::PWM0, ch.get_offs(), Self::NUM_CHANNELS);
+        self.write(register, value)
+    }
+
+    /// Read the pwm of all channels in one transaction into `values`, which must
+    /// hold exactly `NUM_CHANNELS` values.
+    fn read_all_duty(&mut self, values: &mut [u8]) -> Result<(), Error<E>>;
+
+    /// Write the pwm of all channels in one transaction, `values` must contain
+    /// exactly one value per channel.
+    fn write_all_duty(&mut self, values: &[u8]) -> Result<(), Error<E>>;
+
+    /// Write `value` to every channel in one transaction
+    fn write_uniform_duty(&mut self, value: u8) -> Result<(), Error<E>> {
+        let values = [value; MAX_CHANNELS];
+        self.write_all_duty(&values[..Self::NUM_CHANNELS as usize])
+    }
+
+    /// Write the pwm of consecutive channels starting at `start` in one transaction
+    ///
+    /// Uses the brightness-only auto-increment, so only PWM registers are
+    /// touched, e.g. to update one RGB group of several.
+    ///
+    /// *Note: Mirroring set up with `mirror` is not applied*
+    fn write_duty_range(&mut self, start: Self::Channels, values: &[u8]) -> Result<(), Error<E>>;
+
+    /// Write channel pwm from a 16-bit value
+    ///
+    /// *Note: The PWM registers are 8-bit, the lower 8 bits of `value` are discarded*
+    fn write_duty16(&mut self, ch: Self::Channels, value: u16) -> Result<(), E> {
+        self.write_duty(ch, (value >> 8) as u8)
+    }
+
+    /// Write channel pwm as a percentage, clamped to 0-100
+    ///
+    /// Rounds to the nearest step, so 50% is 128.
+    fn write_duty_percent(&mut self, ch: Self::Channels, percent: u8) -> Result<(), E> {
+        self.write_duty(ch, duty_from_percent(percent))
+    }
+
+    /// Write channel pwm as a fraction, clamped to 0.0-1.0
+    ///
+    /// Rounds to the nearest step like `write_duty_percent`, NaN is written as 0.
+    fn write_duty_fraction(&mut self, ch: Self::Channels, frac: f32) -> Result<(), E> {
+        self.write_duty(ch, duty_from_fraction(frac))
+    }
+
+    /// Write channel pwm for a perceived brightness `level` using the [`GAMMA_2_2`] curve
+    fn write_duty_gamma(&mut self, ch: Self::Channels, level: u8) -> Result<(), E> {
+        self.write_duty_with_curve(ch, level, &GAMMA_2_2)
+    }
+
+    /// Write channel pwm `curve[level]`
+    fn write_duty_with_curve(
+        &mut self,
+        ch: Self::Channels,
+        level: u8,
+        curve: &[u8; 256],
+    ) -> Result<(), E> {
+        self.write_duty(ch, curve[level as usize])
+    }
+
+    /// Fade channel pwm linearly from `from` to `to` in `steps` steps, waiting
+    /// `step_delay_us` before each step after the first write of `from`
+    ///
+    /// Nothing is written if `from == to`, `steps == 0` jumps straight to `to`.
+    /// Intermediate values are rounded to the nearest step and the last one is
+    /// always `to`.
+    ///
+    /// *Note: Blocks for the whole fade, use the `PCA963XAsync` variant when
+    /// running under an executor*
+    fn fade_duty<D: DelayUs<u32>>(
+        &mut self,
+        ch: Self::Channels,
+        from: u8,
+        to: u8,
+        steps: u16,
+        step_delay_us: u32,
+        delay: &mut D,
+    ) -> Result<(), E>
+    where
+        Self::Channels: Copy,
+    {
+        if from == to {
+            return Ok(());
+        }
+        if steps == 0 {
+            return self.write_duty(ch, to);
+        }
+        self.write_duty(ch, from)?;
+        for step in 1..=steps {
+            delay.delay_us(step_delay_us);
+            self.write_duty(ch, animation::lerp(from, to, step, steps))?;
+        }
+        Ok(())
+    }
+
+    /// Mix a warm and a cool white channel, `temperature` ranges from fully warm (0) to
+    /// fully cool (255). The two duties always add up to `brightness`.
+    fn set_cct(
+        &mut self,
+        warm_ch: Self::Channels,
+        cool_ch: Self::Channels,
+        temperature: u8,
+        brightness: u8,
+    ) -> Result<(), E> {
+        let cool = (brightness as u16 * temperature as u16 / 255) as u8;
+        self.write_duty(warm_ch, brightness - cool)?;
+        self.write_duty(cool_ch, cool)
     }
 
     /// Write channel output mode
+    ///
+    /// The devices in this crate keep a copy of the LEDOUT registers and only write
+    /// the changed register, see their `sync_cache`.
     fn write_out(&mut self, ch: Self::Channels, out: LedOut) -> Result<(), E> {
-        let offs = ch.get_offs();
-        let mut ledout = self.read(Self::LEDOUT1 + (offs / 4u8))?;
-        ledout &= 0x03 << ((offs % 4u8) * 2);
-        ledout |= (out as u8) << ((offs % 4u8) * 2);
-        self.write(Self::LEDOUT1 + (offs / 4u8), ledout)
+        let (index, shift) = ch.ledout_location();
+        let mut ledout = self.read(Self::LEDOUT1 + index)?;
+        ledout &= !(0x03 << shift);
+        ledout |= (out as u8) << shift;
+        self.write(Self::LEDOUT1 + index, ledout)
+    }
+
+    /// Read channel output mode
+    fn read_out(&mut self, ch: Self::Channels) -> Result<LedOut, E> {
+        let (index, shift) = ch.ledout_location();
+        let ledout = self.read(Self::LEDOUT1 + index)?;
+        Ok(LedOut::from_bits(ledout >> shift))
     }
 
-    ///// Write channel output mode to all outputs
-    //fn write_all_out(&mut self, out: LedOut) -> Result<(), E>;
+    /// Write channel output mode to all outputs
+    fn write_all_out(&mut self, out: LedOut) -> Result<(), E>;
+
+    /// Write the output mode of every channel in one transaction, `modes` must
+    /// contain exactly one mode per channel.
+    fn write_all_out_array(&mut self, modes: &[LedOut]) -> Result<(), Error<E>>;
 
     /// Write group duty cycle
     fn write_group_duty(&mut self, value: u8) -> Result<(), E> {
@@ -427,6 +1905,72 @@ where
         self.write(Self::GRPFREQ, value)
     }
 
+    /// Read group duty cycle
+    fn read_group_duty(&mut self) -> Result<u8, E> {
+        self.read(Self::GRPPWM)
+    }
+
+    /// Read group frequency
+    fn read_group_freq(&mut self) -> Result<u8, E> {
+        self.read(Self::GRPFREQ)
+    }
+
+    /// Enable group blinking with a period of `period_ms` (see `write_group_period_ms`)
+    /// and `duty` on time. Sets DMBLNK in MODE2 and writes GRPPWM and GRPFREQ in
+    /// one transaction. Only channels in `LedOut::PwmGroup` blink.
+    fn configure_blink(&mut self, period_ms: u16, duty: u8) -> Result<(), E>;
+
+    /// Enable group dimming with `duty` by clearing DMBLNK in MODE2 and writing GRPPWM
+    fn configure_dimming(&mut self, duty: u8) -> Result<(), E> {
+        self.update_mode2(|mode2| mode2 - Mode2::DmBlink)?;
+        self.write_group_duty(duty)
+    }
+
+    /// Ramp the group duty cycle from `min` up to `max` and back down in `steps`
+    /// steps each way, waiting `step_delay_us` before each step. Call repeatedly
+    /// for a continuous breathing effect.
+    ///
+    /// Only channels in `LedOut::PwmGroup` follow the group duty cycle, and only
+    /// with DMBLNK cleared, see `configure_dimming`. `steps == 0` writes `min`.
+    /// For the per-channel duty cycles use [`animation::Breathe`] instead.
+    ///
+    /// *Note: Each call starts by writing `min`, back to back calls repeat the
+    /// final `min` of the previous call. The repeat is written without a delay,
+    /// so it costs a bus transaction but doesn't hold `min` any longer.*
+    fn breathe_group<D: DelayUs<u32>>(
+        &mut self,
+        min: u8,
+        max: u8,
+        steps: u16,
+        step_delay_us: u32,
+        delay: &mut D,
+    ) -> Result<(), E> {
+        self.write_group_duty(min)?;
+        if steps == 0 {
+            return Ok(());
+        }
+        for (from, to) in [(min, max), (max, min)] {
+            for step in 1..=steps {
+                delay.delay_us(step_delay_us);
+                self.write_group_duty(animation::lerp(from, to, step, steps))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the group blink period in milliseconds, rounded to the nearest
+    /// 1/24 s step. Periods outside 42ms-10667ms are clamped to the range.
+    /// Not used if `DmBlink` flag is not set in config.
+    fn write_group_period_ms(&mut self, ms: u16) -> Result<(), E> {
+        self.write_group_freq(group_freq_for_period_ms(ms))
+    }
+
+    /// Read the group blink period in milliseconds, rounded to the nearest
+    /// millisecond, from 42ms to 10667ms
+    fn read_group_period_ms(&mut self) -> Result<u16, E> {
+        Ok(period_ms_for_group_freq(self.read_group_freq()?))
+    }
+
     /// Write sub address 1. Requires `Sub1` flag in config to be set.
     fn write_sub_address1(&mut self, addr: u8) -> Result<(), E> {
         self.write(Self::SUBADR1, addr << 1)
@@ -443,139 +1987,2445 @@ where
     }
 
     /// Write all call address. Requires `AllCall` flag in config to be set.
+    ///
+    /// ALLCALLADR only holds the address (0x70 at power-on), the ALLCALL bit of
+    /// MODE1 decides whether the device answers on it.
     fn write_all_call_address1(&mut self, addr: u8) -> Result<(), E> {
         self.write(Self::ALLCALLADR, addr << 1)
     }
+
+    /// Stop answering on the all call address by clearing the ALLCALL bit of MODE1,
+    /// ALLCALLADR is left as is
+    fn disable_all_call(&mut self) -> Result<(), E> {
+        self.update_mode1(|mode1| mode1 - Mode1::AllCall)
+    }
+
+    /// Write sub address `which` (1-3) and set or clear its MODE1 flag, so the
+    /// device starts or stops answering on `addr`. `Error::OutOfRange` for any
+    /// other `which`, `Error::InvalidAddress` if `addr` is rejected by
+    /// [`Address::custom_checked`].
+    fn configure_subaddress(&mut self, which: u8, addr: u8, enable: bool) -> Result<(), Error<E>>
+    where
+        Self: Sized,
+    {
+        let (register, flag) = Self::register(Register::SubAddr(which))
+            .zip(sub_address_flag(which))
+            .ok_or(Error::OutOfRange)?;
+        Address::custom_checked(addr).map_err(|_| Error::InvalidAddress)?;
+        self.write(register, addr << 1)?;
+        Ok(self.update_mode1(|mut mode1| {
+            mode1.set(flag, enable);
+            mode1
+        })?)
+    }
+
+    /// Read sub address `which` (1-3) as a 7-bit address, `Error::OutOfRange`
+    /// for any other `which`
+    fn read_sub_address(&mut self, which: u8) -> Result<u8, Error<E>>
+    where
+        Self: Sized,
+    {
+        let register = Self::register(Register::SubAddr(which)).ok_or(Error::OutOfRange)?;
+        Ok(self.read(register)? >> 1)
+    }
+}
+
+/// MODE1 flag enabling sub address `which`
+fn sub_address_flag(which: u8) -> Option<Mode1> {
+    match which {
+        1 => Some(Mode1::Sub1),
+        2 => Some(Mode1::Sub2),
+        3 => Some(Mode1::Sub3),
+        _ => None,
+    }
+}
+
+/// PWM value nearest to `percent`, clamped to 100%
+const fn duty_from_percent(percent: u8) -> u8 {
+    let percent = if percent > 100 { 100 } else { percent };
+    ((percent as u16 * 255 + 50) / 100) as u8
+}
+
+/// PWM value nearest to `frac`, clamped to 0.0-1.0
+fn duty_from_fraction(frac: f32) -> u8 {
+    (frac.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+/// Gamma 2.2 curve, maps perceived brightness to PWM value, see
+/// [`PCA963X::write_duty_gamma`]
+pub const GAMMA_2_2: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2,
+    3, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10, 10, 11, 11,
+    11, 12, 12, 13, 13, 13, 14, 14, 15, 15, 16, 16, 17, 17, 18, 18, 19, 19, 20, 20, 21, 22, 22, 23,
+    23, 24, 25, 25, 26, 26, 27, 28, 28, 29, 30, 30, 31, 32, 33, 33, 34, 35, 35, 36, 37, 38, 39, 39,
+    40, 41, 42, 43, 43, 44, 45, 46, 47, 48, 49, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61,
+    62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 73, 74, 75, 76, 77, 78, 79, 81, 82, 83, 84, 85, 87, 88,
+    89, 90, 91, 93, 94, 95, 97, 98, 99, 100, 102, 103, 105, 106, 107, 109, 110, 111, 113, 114, 116,
+    117, 119, 120, 121, 123, 124, 126, 127, 129, 130, 132, 133, 135, 137, 138, 140, 141, 143, 145,
+    146, 148, 149, 151, 153, 154, 156, 158, 159, 161, 163, 165, 166, 168, 170, 172, 173, 175, 177,
+    179, 181, 182, 184, 186, 188, 190, 192, 194, 196, 197, 199, 201, 203, 205, 207, 209, 211, 213,
+    215, 217, 219, 221, 223, 225, 227, 229, 231, 234, 236, 238, 240, 242, 244, 246, 248, 251, 253,
+    255,
+];
+
+/// GRPFREQ value for a blink period of `ms`, the period is `(GRPFREQ + 1) / 24` s
+///
+/// Computed in `u32`, so `ms * 24` can't overflow, and rounded to the nearest step.
+const fn group_freq_for_period_ms(ms: u16) -> u8 {
+    let steps = (ms as u32 * 24 + 500) / 1000;
+    if steps == 0 {
+        0
+    } else if steps > 256 {
+        255
+    } else {
+        (steps - 1) as u8
+    }
+}
+
+/// Blink period in ms of `freq`, inverse of `group_freq_for_period_ms`
+const fn period_ms_for_group_freq(freq: u8) -> u16 {
+    (((freq as u32 + 1) * 1000 + 12) / 24) as u16
+}
+
+/// PWM register of the channel at `offs`, debug builds panic if it is past the
+/// last of `channels` PWM registers instead of silently writing a group or
+/// LEDOUT register
+const fn pwm_register(pwm0: u8, offs: u8, channels: u8) -> u8 {
+    debug_assert!(offs < channels, "channel offset beyond the PWM registers");
+    pwm0 + offs
+}
+
+/// The PWM registers must end where the group registers begin
+const fn pwm_span_ok(pwm0: u8, channels: u8, grppwm: u8) -> bool {
+    pwm0 + channels == grppwm
+}
+
+/// Driver for a part `M` with `CH` channels
+///
+/// Use the aliases for the supported parts, [`PCA9633`], [`PCA9634`],
+/// [`PCA9635`] and [`PCA9624`]. Other parts with the same register layout
+/// can be added with a [`Part`] marker, see [`part`].
+pub struct Pca963x<I2C, M, const CH: usize, OE = ()> {
+    i2c: I2C,
+    address: u8,
+    duties: [u8; CH],
+    mirrors: [u16; CH],
+    inverted: u16,
+    ledouts: [u8; MAX_LEDOUT],
+    enable_mode: LedOut,
+    oe: OE,
+    _part: PhantomData<M>,
+}
+
+impl<I2C, M: Part, const CH: usize, OE> Pca963x<I2C, M, CH, OE> {
+    /// Checks of the register map, evaluated when the device type is built
+    const MAP_OK: () = {
+        assert!(
+            CH <= MAX_CHANNELS && M::Channels::ALL.len() == CH,
+            "channel count doesn't match the channel type"
+        );
+        assert!(
+            pwm_span_ok(M::PWM0, CH as u8, M::GRPPWM),
+            "PWM registers overlap the group registers"
+        );
+        assert!(
+            M::ALLCALLADR as usize == register_count(CH) - 1,
+            "registers are not contiguous"
+        );
+    };
 }
 
-macro_rules! device {
-    ($name:ident, $channels:ident => $($reg:ident = $val:expr);*) => {
-        pub struct $name<I2C> {
-            i2c: I2C,
-            address: u8
+#[cfg(feature = "trace")]
+impl<I2C, M: Part, const CH: usize, OE> Pca963x<I2C, M, CH, OE> {
+    /// Name of the register at `offset` for the transaction log, `None`
+    /// if there is none (e.g. auto-increment flags set)
+    fn register_name(offset: u8) -> Option<Register> {
+        match offset {
+            o if o == M::MODE1 => Some(Register::Mode1),
+            o if o == M::MODE2 => Some(Register::Mode2),
+            o if (M::PWM0..M::GRPPWM).contains(&o) => Some(Register::Pwm(o - M::PWM0)),
+            o if o == M::GRPPWM => Some(Register::GrpPwm),
+            o if o == M::GRPFREQ => Some(Register::GrpFreq),
+            o if (M::LEDOUT1..M::SUBADR1).contains(&o) => Some(Register::LedOut(o - M::LEDOUT1)),
+            o if o == M::SUBADR1 => Some(Register::SubAddr(1)),
+            o if o == M::SUBADR2 => Some(Register::SubAddr(2)),
+            o if o == M::SUBADR3 => Some(Register::SubAddr(3)),
+            o if o == M::ALLCALLADR => Some(Register::AllCallAddr),
+            _ => None,
         }
+    }
+}
 
-        impl<I2C, E> PCA963X<I2C, E> for $name<I2C>
-            where I2C: i2c::Write<Error = E> + i2c::Read<Error = E> {
+impl<I2C, M: Part, const CH: usize, OE, E> PCA963X<I2C, E> for Pca963x<I2C, M, CH, OE>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    const MODE1: u8 = M::MODE1;
+    const MODE2: u8 = M::MODE2;
+    const PWM0: u8 = M::PWM0;
+    const GRPPWM: u8 = M::GRPPWM;
+    const GRPFREQ: u8 = M::GRPFREQ;
+    const LEDOUT1: u8 = M::LEDOUT1;
+    const SUBADR1: u8 = M::SUBADR1;
+    const SUBADR2: u8 = M::SUBADR2;
+    const SUBADR3: u8 = M::SUBADR3;
+    const ALLCALLADR: u8 = M::ALLCALLADR;
 
-            $(
-                const $reg : u8 = $val;
-            )*
+    const NUM_CHANNELS: u8 = CH as u8;
+    const NUM_LEDOUT: u8 = ledout_count(CH) as u8;
 
-            type Channels = $channels;
+    type Channels = M::Channels;
 
-            fn read(&mut self, register: u8) -> Result<u8, E> {
-                let mut buf = [0u8];
-                self.i2c.write(self.address, &[register])?;
-                self.i2c.read(self.address, &mut buf)?;
-                Ok(buf[0])
-            }
+    fn read(&mut self, register: u8) -> Result<u8, E> {
+        let mut buf = [0u8];
+        self.bus_read(register, &mut buf)?;
+        Ok(buf[0])
+    }
 
-            fn write(&mut self, register: u8, value: u8) -> Result<(), E> {
-                self.i2c.write(self.address, &[register, value])
-            }
+    fn write(&mut self, register: u8, value: u8) -> Result<(), E> {
+        self.bus_write(register, &[value])
+    }
 
-            fn write_config(&mut self, conf: Config) -> Result<(), E> {
-                self.i2c.write(self.address, &[AUTOINCR_ALL | Self::MODE1, conf.mode1.bits, conf.mode2.bits])
-            }
+    fn read_modes_raw(&mut self) -> Result<(u8, u8), E> {
+        let mut modes = [0u8; 2];
+        self.bus_read(AUTOINCR_ALL | Self::MODE1, &mut modes)?;
+        Ok((modes[0], modes[1]))
+    }
+
+    fn write_modes_raw(&mut self, mode1: u8, mode2: u8) -> Result<(), E> {
+        self.bus_write(AUTOINCR_ALL | Self::MODE1, &[mode1, mode2])
+    }
+
+    fn recommended_config() -> Config {
+        M::recommended_config()
+    }
+
+    fn supports_config(conf: &Config) -> bool {
+        M::supports_config(conf)
+    }
+
+    fn write_all_out(&mut self, out: LedOut) -> Result<(), E> {
+        self.write_ledouts(&[(out as u8) * 0b0101_0101; MAX_LEDOUT])
+    }
+
+    fn write_all_out_array(&mut self, modes: &[LedOut]) -> Result<(), Error<E>> {
+        if modes.len() != CH {
+            return Err(Error::InvalidLength);
         }
+        Ok(self.write_ledouts(&pack_ledout(modes))?)
+    }
 
-        impl<I2C, E> $name<I2C>
-            where I2C: i2c::Write<Error = E> + i2c::Read<Error = E>{
+    fn read_all_duty(&mut self, values: &mut [u8]) -> Result<(), Error<E>> {
+        if values.len() != CH {
+            return Err(Error::InvalidLength);
+        }
+        values.copy_from_slice(&self.read_frame()?.0);
+        Ok(())
+    }
 
-            /// New LED driver
-            ///
-            /// *Note: Does not take driver out of __sleep__ mode*
-            pub fn new(i2c: I2C, address: Address) -> Self {
-                $name {
-                    i2c,
-                    address: address.address()
-                }
-            }
+    fn write_all_duty(&mut self, values: &[u8]) -> Result<(), Error<E>> {
+        let mut frame = DutyFrame::default();
+        if values.len() != frame.0.len() {
+            return Err(Error::InvalidLength);
+        }
+        frame.0.copy_from_slice(values);
+        Ok(self.write_frame(&frame)?)
+    }
 
-            /// New LED driver
-            pub fn new_config(i2c: I2C, address: Address, conf: Config) -> Result<Self, E> {
-                let mut pca = Self::new(i2c, address);
-                pca.write_config(conf)?;
-                Ok(pca)
-            }
+    fn write_out(&mut self, ch: Self::Channels, out: LedOut) -> Result<(), E> {
+        self.write_out_offs(ch.get_offs(), out)
+    }
+
+    fn write_uniform_duty(&mut self, value: u8) -> Result<(), Error<E>> {
+        self.write_all_duty(&[value; CH])
+    }
+
+    fn configure_blink(&mut self, period_ms: u16, duty: u8) -> Result<(), E> {
+        self.update_mode2(|mode2| mode2 | Mode2::DmBlink)?;
+        let freq = group_freq_for_period_ms(period_ms);
+        self.bus_write(AUTOINCR_ALL | Self::GRPPWM, &[duty, freq])
+    }
+
+    fn write_registers(
+        &mut self,
+        start: u8,
+        mode: AutoIncrement,
+        data: &[u8],
+    ) -> Result<(), Error<E>> {
+        if start > Self::ALLCALLADR {
+            return Err(Error::OutOfRange);
         }
+        if data.len() > MAX_REGISTER_SPAN {
+            return Err(Error::InvalidLength);
+        }
+        Ok(self.bus_write(mode as u8 | start, data)?)
+    }
 
-        #[cfg(feature="embedded-hal-pwm")]
-        impl<I2C, E> hal::Pwm for $name<I2C>
-        where
-            I2C: i2c::Write<Error = E> + i2c::Read<Error = E>
-        {
-            type Channel = <$name<I2C> as PCA963X<I2C, E>>::Channels;
-            type Time = ();
-            type Duty = u8;
+    fn read_registers(
+        &mut self,
+        start: u8,
+        mode: AutoIncrement,
+        buf: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        if start > Self::ALLCALLADR {
+            return Err(Error::OutOfRange);
+        }
+        Ok(self.bus_read(mode as u8 | start, buf)?)
+    }
 
-            fn disable(&mut self, channel: Self::Channel) {
-                self.write_out(channel, LedOut::FullyOff).unwrap_or_default()
-            }
+    fn write_duty_range(&mut self, start: Self::Channels, values: &[u8]) -> Result<(), Error<E>> {
+        let offs = start.get_offs() as usize;
+        if offs + values.len() > CH {
+            return Err(Error::InvalidLength);
+        }
+        let mut buf = [0u8; MAX_CHANNELS];
+        for (i, duty) in values.iter().enumerate() {
+            buf[i] = self.invert_duty(offs + i, *duty);
+        }
+        self.bus_write(
+            AUTOINCR_BRIGHT | (Self::PWM0 + offs as u8),
+            &buf[..values.len()],
+        )?;
+        self.duties[offs..offs + values.len()].copy_from_slice(values);
+        Ok(())
+    }
 
-            fn enable(&mut self, channel: Self::Channel) {
-                self.write_out(channel, LedOut::Pwm).unwrap_or_default()
-            }
+    fn read_duty(&mut self, ch: Self::Channels) -> Result<u8, E> {
+        let offs = ch.get_offs();
+        let value = self.read(pwm_register(Self::PWM0, offs, CH as u8))?;
+        Ok(self.invert_duty(offs as usize, value))
+    }
 
-            fn get_period(&self) -> Self::Time {
-                ()
-            }
+    fn write_duty(&mut self, ch: Self::Channels, value: u8) -> Result<(), E> {
+        let offs = ch.get_offs();
+        debug_assert!(
+            (offs as usize) < CH,
+            "channel offset beyond the PWM registers"
+        );
+        self.write_linked(self.mirrors[offs as usize] | 1 << offs, value)
+    }
+}
 
-            fn get_duty(&self, _channel: Self::Channel) -> Self::Duty {
-                //self.read_duty(channel).unwrap_or(0)
-                0
-            }
+#[cfg(feature = "async")]
+impl<I2C, M: Part, const CH: usize, OE, E> PCA963XAsync<I2C, E> for Pca963x<I2C, M, CH, OE>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    const MODE1: u8 = M::MODE1;
+    const MODE2: u8 = M::MODE2;
+    const PWM0: u8 = M::PWM0;
+    const GRPPWM: u8 = M::GRPPWM;
+    const GRPFREQ: u8 = M::GRPFREQ;
+    const LEDOUT1: u8 = M::LEDOUT1;
+    const SUBADR1: u8 = M::SUBADR1;
+    const SUBADR2: u8 = M::SUBADR2;
+    const SUBADR3: u8 = M::SUBADR3;
+    const ALLCALLADR: u8 = M::ALLCALLADR;
 
-            fn get_max_duty(&self) -> Self::Duty {
-                255u8
-            }
+    const NUM_CHANNELS: u8 = CH as u8;
+    const NUM_LEDOUT: u8 = ledout_count(CH) as u8;
 
-            fn set_duty(&mut self, channel: Self::Channel, duty: Self::Duty) {
-                self.write_duty(channel, duty).unwrap_or_default()
-            }
+    type Channels = M::Channels;
 
-            fn set_period<P>(&mut self, _period: P) where
-                P: Into<Self::Time> {
+    async fn read(&mut self, register: u8) -> Result<u8, E> {
+        let mut buf = [0u8];
+        self.bus_read_async(register, &mut buf).await?;
+        Ok(buf[0])
+    }
 
-            }
+    async fn write(&mut self, register: u8, value: u8) -> Result<(), E> {
+        self.bus_write_async(register, &[value]).await
+    }
+
+    async fn read_modes_raw(&mut self) -> Result<(u8, u8), E> {
+        let mut modes = [0u8; 2];
+        self.bus_read_async(AUTOINCR_ALL | Self::MODE1, &mut modes)
+            .await?;
+        Ok((modes[0], modes[1]))
+    }
+
+    async fn write_modes_raw(&mut self, mode1: u8, mode2: u8) -> Result<(), E> {
+        self.bus_write_async(AUTOINCR_ALL | Self::MODE1, &[mode1, mode2])
+            .await
+    }
+
+    async fn write_all_out(&mut self, out: LedOut) -> Result<(), E> {
+        let ledouts = [(out as u8) * 0b0101_0101; MAX_LEDOUT];
+        self.bus_write_async(AUTOINCR_ALL | Self::LEDOUT1, &ledouts[..ledout_count(CH)])
+            .await?;
+        self.ledouts = ledouts;
+        Ok(())
+    }
+
+    async fn write_all_out_array(&mut self, modes: &[LedOut]) -> Result<(), Error<E>> {
+        if modes.len() != CH {
+            return Err(Error::InvalidLength);
         }
-    };
-}
+        let ledouts: [u8; MAX_LEDOUT] = pack_ledout(modes);
+        self.bus_write_async(AUTOINCR_ALL | Self::LEDOUT1, &ledouts[..ledout_count(CH)])
+            .await?;
+        self.ledouts = ledouts;
+        Ok(())
+    }
 
-device!(PCA9633, Channels4 =>
-    MODE1 = 0x00;
-    MODE2 = 0x01;
-    PWM0  = 0x02;
-    //PWM1 = 0x03;
-    //PWM2 = 0x04;
-    //PWM3 = 0x05;
-    GRPPWM = 0x06;
-    GRPFREQ = 0x07;
-    LEDOUT1 = 0x08;
-    SUBADR1 = 0x09;
-    SUBADR2 = 0x0A;
-    SUBADR3 = 0x0B;
-    ALLCALLADR = 0x0C
-);
+    async fn write_out(&mut self, ch: Self::Channels, out: LedOut) -> Result<(), E> {
+        let offs = ch.get_offs();
+        let index = ledout_location(offs).0;
+        let ledout = self.ledout_with(offs, out);
+        self.write(Self::LEDOUT1 + index, ledout).await?;
+        self.ledouts[index as usize] = ledout;
+        Ok(())
+    }
 
-device!(PCA9634, Channels8 =>
-    MODE1 = 0x00;
-    MODE2 = 0x01;
-    PWM0 = 0x02;
-    //PWM1 = 0x03;
-    //PWM2 = 0x04;
-    //PWM3 = 0x05;
-    //PWM4 = 0x06;
-    //PWM5 = 0x07;
-    //PWM6 = 0x08;
-    //PWM7 = 0x09;
-    GRPPWM = 0x0A;
-    GRPFREQ = 0x0B;
-    LEDOUT1 = 0x0C;
-    //LEDOUT2 = 0x0D;
-    SUBADR1 = 0x0E;
-    SUBADR2 = 0x0F;
-    SUBADR3 = 0x10;
-    ALLCALLADR = 0x11
-);
+    async fn read_all_duty(&mut self, values: &mut [u8]) -> Result<(), Error<E>> {
+        if values.len() != CH {
+            return Err(Error::InvalidLength);
+        }
+        let mut duties = [0u8; CH];
+        self.bus_read_async(AUTOINCR_ALL | Self::PWM0, &mut duties)
+            .await?;
+        for (i, duty) in duties.iter_mut().enumerate() {
+            *duty = self.invert_duty(i, *duty);
+        }
+        self.duties = duties;
+        values.copy_from_slice(&duties);
+        Ok(())
+    }
+
+    async fn write_all_duty(&mut self, values: &[u8]) -> Result<(), Error<E>> {
+        if values.len() != CH {
+            return Err(Error::InvalidLength);
+        }
+        let mut buf = [0u8; CH];
+        for (i, duty) in values.iter().enumerate() {
+            buf[i] = self.invert_duty(i, *duty);
+        }
+        self.bus_write_async(AUTOINCR_ALL | Self::PWM0, &buf)
+            .await?;
+        self.duties.copy_from_slice(values);
+        Ok(())
+    }
+
+    async fn read_duty(&mut self, ch: Self::Channels) -> Result<u8, E> {
+        let offs = ch.get_offs();
+        let value = self.read(pwm_register(Self::PWM0, offs, CH as u8)).await?;
+        Ok(self.invert_duty(offs as usize, value))
+    }
+
+    /// Mirrored channels are written in one transaction, see `write_linked`
+    async fn write_duty(&mut self, ch: Self::Channels, value: u8) -> Result<(), E> {
+        let offs = ch.get_offs();
+        debug_assert!(
+            (offs as usize) < CH,
+            "channel offset beyond the PWM registers"
+        );
+        let linked = self.mirrors[offs as usize] | 1 << offs;
+        let mut span = self.linked_span(linked);
+        if span.gaps {
+            self.bus_read_async(span.pointer, span.duties()).await?;
+        }
+        self.fill_linked(&mut span, value);
+        self.bus_write_async(span.pointer, span.duties()).await?;
+        self.cache_linked(linked, value);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C, M: Part, const CH: usize, OE, E> Pca963x<I2C, M, CH, OE>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    /// Async `bus_write`, every async write to the device goes through here
+    async fn bus_write_async(&mut self, pointer: u8, data: &[u8]) -> Result<(), E> {
+        trace_register!("write", self.address, pointer, data);
+        let mut buf = [0u8; MAX_REGISTER_SPAN + 1];
+        buf[0] = pointer;
+        buf[1..data.len() + 1].copy_from_slice(data);
+        self.i2c.write(self.address, &buf[..data.len() + 1]).await
+    }
+
+    /// Async `bus_read`, every async read from the device goes through here
+    async fn bus_read_async(&mut self, pointer: u8, buf: &mut [u8]) -> Result<(), E> {
+        self.i2c.write_read(self.address, &[pointer], buf).await?;
+        trace_register!("read", self.address, pointer, buf);
+        Ok(())
+    }
+}
+
+impl<I2C, M: Part, const CH: usize> Pca963x<I2C, M, CH> {
+    /// New LED driver
+    ///
+    /// *Note: Does not take driver out of __sleep__ mode*
+    pub fn new(i2c: I2C, address: Address) -> Self {
+        Self::new_with_oe(i2c, address, ())
+    }
+
+    /// New LED driver on the 7-bit `address`, e.g. one found by [`scan`]
+    ///
+    /// Not validated, same as `Address::Custom(address)`.
+    pub fn new_raw(i2c: I2C, address: u8) -> Self {
+        Self::new(i2c, Address::Custom(address))
+    }
+
+    /// New LED driver with its /OE pin driven by `oe`, see `enable_outputs`
+    ///
+    /// The pin is left as is, outputs are enabled while it is low.
+    ///
+    /// *Note: Does not take driver out of __sleep__ mode*
+    pub fn new_with_oe<P>(i2c: I2C, address: Address, oe: P) -> Pca963x<I2C, M, CH, P> {
+        let () = Pca963x::<I2C, M, CH, P>::MAP_OK;
+        Pca963x {
+            i2c,
+            address: address.address(),
+            duties: [0; CH],
+            mirrors: [0; CH],
+            inverted: 0,
+            ledouts: [0; MAX_LEDOUT],
+            enable_mode: LedOut::Pwm,
+            oe,
+            _part: PhantomData,
+        }
+    }
+}
+
+impl<I2C, M, const CH: usize, P: OutputPin> Pca963x<I2C, M, CH, P> {
+    /// Drive /OE low, the outputs follow their LEDOUT modes
+    pub fn enable_outputs(&mut self) -> Result<(), P::Error> {
+        self.oe.set_low()
+    }
+
+    /// Drive /OE high, the outputs take the state selected by the `OutNe`
+    /// bits of MODE2, see [`Config::outne`]
+    pub fn disable_outputs(&mut self) -> Result<(), P::Error> {
+        self.oe.set_high()
+    }
+}
+
+impl<I2C, M: Part, const CH: usize, OE> Pca963x<I2C, M, CH, OE> {
+    /// Release the I2C bus, the device is left in its current state
+    pub fn destroy(self) -> I2C {
+        self.i2c
+    }
+
+    /// Release the I2C bus and the /OE pin, see `destroy`
+    pub fn destroy_with_oe(self) -> (I2C, OE) {
+        (self.i2c, self.oe)
+    }
+
+    /// 7-bit address transactions are sent to
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Send following transactions to `address`, e.g. a sub-address or the all
+    /// call address
+    ///
+    /// *Note: Nothing is written to the device, use `configure_subaddress` or
+    /// `write_all_call_address1` to change the addresses it answers to*
+    pub fn set_address(&mut self, address: Address) {
+        self.address = address.address();
+    }
+
+    /// Handle to channel `ch` alone, see [`PcaChannel`]
+    pub fn channel(&mut self, ch: M::Channels) -> PcaChannel<'_, Self> {
+        PcaChannel {
+            pca: self,
+            offs: ch.get_offs(),
+        }
+    }
+
+    /// Mirror `source` onto `targets`, every following `write_duty` to `source`
+    /// also writes the same value to `targets`. An empty `targets` removes the mirroring.
+    pub fn mirror(&mut self, source: M::Channels, targets: &[M::Channels]) {
+        let mut linked = 0u16;
+        for target in targets {
+            linked |= 1 << target.get_offs();
+        }
+        self.mirrors[source.get_offs() as usize] = linked;
+    }
+
+    /// Invert the duty cycle of `ch` in software, the written PWM value becomes
+    /// `255 - value`. Unlike the `Invert` config flag this only affects a single
+    /// channel and is applied to `write_duty` and frame reads/writes.
+    pub fn set_channel_inverted(&mut self, ch: M::Channels, inverted: bool) {
+        if inverted {
+            self.inverted |= 1 << ch.get_offs();
+        } else {
+            self.inverted &= !(1 << ch.get_offs());
+        }
+    }
+
+    /// Output mode written by `Pwm::enable` and `try_enable`, `LedOut::Pwm`
+    /// unless changed with `set_enable_mode`
+    pub fn enable_mode(&self) -> LedOut {
+        self.enable_mode
+    }
+
+    /// Use `out` when enabling a channel, e.g. `LedOut::PwmGroup` to keep
+    /// enabled channels under group dimming/blinking control
+    pub fn set_enable_mode(&mut self, out: LedOut) {
+        self.enable_mode = out;
+    }
+
+    /// Cached LEDOUT register of channel `offs` with its output mode set to `out`
+    fn ledout_with(&self, offs: u8, out: LedOut) -> u8 {
+        let (index, shift) = ledout_location(offs);
+        let ledout = self.ledouts[index as usize] & !(0x03 << shift);
+        ledout | (out as u8) << shift
+    }
+
+    /// Apply software inversion of channel `offs` to `value`
+    fn invert_duty(&self, offs: usize, value: u8) -> u8 {
+        if self.inverted & 1 << offs != 0 {
+            !value
+        } else {
+            value
+        }
+    }
+
+    /// PWM registers from the lowest to the highest channel in `linked`, a
+    /// single channel is addressed without auto-increment
+    fn linked_span(&self, linked: u16) -> LinkedSpan {
+        let lo = linked.trailing_zeros() as usize;
+        let hi = 15 - linked.leading_zeros() as usize;
+        let pointer = if lo == hi {
+            M::PWM0 + lo as u8
+        } else {
+            AUTOINCR_ALL | (M::PWM0 + lo as u8)
+        };
+        let span_mask = ((1u32 << (hi + 1)) - (1u32 << lo)) as u16;
+        LinkedSpan {
+            linked,
+            lo,
+            pointer,
+            buf: [0u8; MAX_CHANNELS],
+            len: hi - lo + 1,
+            gaps: linked != span_mask,
+        }
+    }
+
+    /// Set the linked channels of `span` to `value`, inverted where needed. The
+    /// others keep what was read into the span.
+    fn fill_linked(&self, span: &mut LinkedSpan, value: u8) {
+        let (linked, lo) = (span.linked, span.lo);
+        for (i, duty) in span.duties().iter_mut().enumerate() {
+            if linked & 1 << (lo + i) != 0 {
+                *duty = self.invert_duty(lo + i, value);
+            }
+        }
+    }
+
+    /// Record `value` as the duty of every channel in `linked`
+    fn cache_linked(&mut self, linked: u16, value: u8) {
+        for (i, duty) in self.duties.iter_mut().enumerate() {
+            if linked & 1 << i != 0 {
+                *duty = value;
+            }
+        }
+    }
+}
+
+/// Register pointer and PWM values of a linked write, see `write_linked`
+struct LinkedSpan {
+    linked: u16,
+    /// Offset of the first channel in the span
+    lo: usize,
+    pointer: u8,
+    buf: [u8; MAX_CHANNELS],
+    len: usize,
+    /// Channels in the span which aren't linked, they have to be read first
+    gaps: bool,
+}
+
+impl LinkedSpan {
+    fn duties(&mut self) -> &mut [u8] {
+        &mut self.buf[..self.len]
+    }
+}
+
+impl<I2C, M: Part, const CH: usize, E> Pca963x<I2C, M, CH>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    /// New LED driver, writing `conf` to the device
+    ///
+    /// Any bus error during setup is returned and the driver dropped.
+    pub fn new_config(i2c: I2C, address: Address, conf: Config) -> Result<Self, E> {
+        let mut pca = Self::new(i2c, address);
+        pca.write_config(conf)?;
+        Ok(pca)
+    }
+
+    /// New LED driver, awake and ready to drive the outputs
+    ///
+    /// Writes `M::recommended_config()` (see [`PCA963X::recommended_config`])
+    /// with SLEEP cleared and waits for the oscillator to start, so PWM written
+    /// afterwards takes effect right away.
+    ///
+    /// *Note: The recommended config is part specific, e.g. the PCA9634 gets
+    /// open-drain outputs with OUTNE = 10 instead of the power-on MODE2. Use
+    /// `new_config_awake` with `Config::new()` to keep the power-on output
+    /// stage.*
+    pub fn new_awake<D: DelayUs<u16>>(
+        i2c: I2C,
+        address: Address,
+        delay: &mut D,
+    ) -> Result<Self, E> {
+        let conf = <Self as PCA963X<I2C, E>>::recommended_config();
+        Self::new_config_awake(i2c, address, conf, delay)
+    }
+
+    /// New LED driver, writing `conf` with SLEEP cleared and waiting for the
+    /// oscillator to start
+    ///
+    /// *Note: Overrides `.sleep(true)` in `conf`, a device left asleep would
+    /// make the delay pointless. Use `new_config` to keep it asleep.*
+    pub fn new_config_awake<D: DelayUs<u16>>(
+        i2c: I2C,
+        address: Address,
+        conf: Config,
+        delay: &mut D,
+    ) -> Result<Self, E> {
+        let pca = Self::new_config(i2c, address, conf.sleep(false))?;
+        delay.delay_us(OSC_SETTLE_US);
+        Ok(pca)
+    }
+}
+
+impl<I2C, M: Part, const CH: usize, OE, E> Pca963x<I2C, M, CH, OE>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    /// Write `data` to the registers from `pointer` on in one transaction
+    ///
+    /// Every write to the device goes through here, so that the `trace`
+    /// feature logs all of them.
+    fn bus_write(&mut self, pointer: u8, data: &[u8]) -> Result<(), E> {
+        trace_register!("write", self.address, pointer, data);
+        let mut buf = [0u8; MAX_REGISTER_SPAN + 1];
+        buf[0] = pointer;
+        buf[1..data.len() + 1].copy_from_slice(data);
+        self.i2c.write(self.address, &buf[..data.len() + 1])
+    }
+
+    /// Read the registers from `pointer` on into `buf` in one transaction, the
+    /// counterpart of `bus_write`
+    fn bus_read(&mut self, pointer: u8, buf: &mut [u8]) -> Result<(), E> {
+        self.i2c.write_read(self.address, &[pointer], buf)?;
+        trace_register!("read", self.address, pointer, buf);
+        Ok(())
+    }
+
+    /// Write `value` to all channels in the `linked` bitmask in one transaction,
+    /// channels in between are read first and written back unchanged.
+    fn write_linked(&mut self, linked: u16, value: u8) -> Result<(), E> {
+        let mut span = self.linked_span(linked);
+        if span.gaps {
+            self.bus_read(span.pointer, span.duties())?;
+        }
+        self.fill_linked(&mut span, value);
+        self.bus_write(span.pointer, span.duties())?;
+        self.cache_linked(linked, value);
+        Ok(())
+    }
+
+    /// Write the duty cycle of all channels in one transaction
+    pub fn write_frame(&mut self, frame: &DutyFrame<CH>) -> Result<(), E> {
+        let mut buf = [0u8; CH];
+        for (i, duty) in frame.0.iter().enumerate() {
+            buf[i] = self.invert_duty(i, *duty);
+        }
+        self.bus_write(AUTOINCR_ALL | Self::PWM0, &buf)?;
+        self.duties = frame.0;
+        Ok(())
+    }
+
+    /// Read the duty cycle of all channels
+    pub fn read_frame(&mut self) -> Result<DutyFrame<CH>, E> {
+        let mut frame = DutyFrame::default();
+        self.bus_read(AUTOINCR_ALL | Self::PWM0, &mut frame.0)?;
+        for (i, duty) in frame.0.iter_mut().enumerate() {
+            *duty = self.invert_duty(i, *duty);
+        }
+        self.duties = frame.0;
+        Ok(frame)
+    }
+
+    /// Read the output mode of all channels in one transaction
+    pub fn read_all_out(&mut self) -> Result<[LedOut; CH], E> {
+        let mut ledouts = [0u8; MAX_LEDOUT];
+        self.bus_read(
+            AUTOINCR_ALL | Self::LEDOUT1,
+            &mut ledouts[..ledout_count(CH)],
+        )?;
+        self.ledouts = ledouts;
+        Ok(unpack_ledout(&ledouts))
+    }
+
+    /// Bring up the device: restore the power-on register values if `reset` is
+    /// set (see `reset_to_defaults`), write `config` with sleep mode disabled,
+    /// wait for the oscillator, set the output modes to `outputs`, zero all duty
+    /// cycles and verify MODE1/MODE2 by reading them back.
+    pub fn bring_up<D: DelayUs<u16>>(
+        &mut self,
+        reset: bool,
+        config: Config,
+        outputs: &[LedOut; CH],
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        if reset {
+            self.reset_to_defaults()?;
+        }
+        let config = config.sleep(false);
+        self.write_config_checked(config)?;
+        delay.delay_us(OSC_SETTLE_US);
+
+        self.write_ledouts(&pack_ledout(outputs))?;
+        self.write_frame(&DutyFrame::default())?;
+
+        let read = self.read_config()?;
+        if read.mode1 != config.mode1 || read.mode2 != config.mode2 {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
+    }
+
+    /// Write channel pwm, `Error::NotPwm` without writing if the channel's
+    /// output mode is `FullyOff` or `FullyOn`
+    ///
+    /// The PWM register is only used in the `Pwm` and `PwmGroup` modes, a plain
+    /// `write_duty` succeeds but won't change the output in the other two. The
+    /// mode comes from the copy `write_out` keeps, see `sync_cache`.
+    pub fn write_duty_checked(&mut self, ch: M::Channels, value: u8) -> Result<(), Error<E>> {
+        let (index, shift) = ch.ledout_location();
+        let out = LedOut::from_bits(self.ledouts[index as usize] >> shift);
+        match out {
+            LedOut::Pwm | LedOut::PwmGroup => Ok(self.write_duty(ch, value)?),
+            _ => Err(Error::NotPwm(out)),
+        }
+    }
+
+    /// Set the output mode of every channel one at a time, waiting `stagger_us`
+    /// between channels to spread out the inrush current of turning them on
+    ///
+    /// `modes` must contain exactly one mode per channel. Only the moment each
+    /// channel is enabled is staggered, all PWM outputs still switch on at the
+    /// same point of the PWM period afterwards.
+    pub fn staggered_enable<D: DelayUs<u32>>(
+        &mut self,
+        modes: &[LedOut],
+        stagger_us: u32,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        if modes.len() != CH {
+            return Err(Error::InvalidLength);
+        }
+        for (offs, out) in modes.iter().enumerate() {
+            if offs > 0 {
+                delay.delay_us(stagger_us);
+            }
+            self.write_out_offs(offs as u8, *out)?;
+        }
+        Ok(())
+    }
+
+    /// Leave sleep mode and write the cached LEDOUT and PWM values again
+    ///
+    /// The registers survive sleep, this is for when they may not have, e.g.
+    /// after a brown-out or a software reset of the bus while asleep.
+    pub fn wake_and_resume(&mut self) -> Result<(), E> {
+        self.wake()?;
+        let ledouts = self.ledouts;
+        self.write_ledouts(&ledouts)?;
+        self.write_frame(&DutyFrame(self.duties))
+    }
+
+    /// Write the output mode of the channel at `offs`, keeping the others
+    fn write_out_offs(&mut self, offs: u8, out: LedOut) -> Result<(), E> {
+        let index = ledout_location(offs).0;
+        let ledout = self.ledout_with(offs, out);
+        self.write(Self::LEDOUT1 + index, ledout)?;
+        self.ledouts[index as usize] = ledout;
+        Ok(())
+    }
+
+    /// Write all LEDOUT registers in one transaction
+    fn write_ledouts(&mut self, ledouts: &[u8; MAX_LEDOUT]) -> Result<(), E> {
+        self.bus_write(AUTOINCR_ALL | Self::LEDOUT1, &ledouts[..ledout_count(CH)])?;
+        self.ledouts = *ledouts;
+        Ok(())
+    }
+
+    /// Re-read the LEDOUT registers into the copy `write_out` modifies
+    ///
+    /// The copy assumes this driver is the only bus master writing LEDOUT. Sync
+    /// after a reset, after other masters changed them or after raw `write`s.
+    pub fn sync_cache(&mut self) -> Result<(), E> {
+        let mut ledouts = [0u8; MAX_LEDOUT];
+        self.bus_read(
+            AUTOINCR_ALL | Self::LEDOUT1,
+            &mut ledouts[..ledout_count(CH)],
+        )?;
+        self.ledouts = ledouts;
+        Ok(())
+    }
+
+    /// Write the power-on value of every register in one transaction
+    ///
+    /// The device is left asleep with all outputs off, the programmed sub-addresses
+    /// and all call address are restored too. Unlike [`software_reset`] only this
+    /// device is affected. Mirroring and inversion set up on the driver are kept.
+    pub fn reset_to_defaults(&mut self) -> Result<(), E> {
+        let subs = SubAddresses::default();
+        let mut regs = [0u8; register_count(MAX_CHANNELS)];
+        regs[Self::MODE1 as usize] = 0x11;
+        regs[Self::MODE2 as usize] = 0x05;
+        regs[Self::GRPPWM as usize] = 0xFF;
+        regs[Self::SUBADR1 as usize] = subs.sub1 << 1;
+        regs[Self::SUBADR2 as usize] = subs.sub2 << 1;
+        regs[Self::SUBADR3 as usize] = subs.sub3 << 1;
+        regs[Self::ALLCALLADR as usize] = subs.all_call << 1;
+        self.bus_write(AUTOINCR_ALL | Self::MODE1, &regs[..register_count(CH)])?;
+        self.duties = [0; CH];
+        self.ledouts = [0; MAX_LEDOUT];
+        Ok(())
+    }
+
+    /// Rotate the current duty cycles `by` channels, towards higher channels if
+    /// positive and lower if negative.
+    pub fn rotate_duties(&mut self, by: i8) -> Result<(), E> {
+        let mut frame = self.read_frame()?;
+        let n = (by as isize).rem_euclid(CH as isize) as usize;
+        frame.0.rotate_right(n);
+        self.write_frame(&frame)
+    }
+
+    /// Write the frame `t/255` of the way from `from` to `to`
+    pub fn crossfade(&mut self, from: &DutyFrame<CH>, to: &DutyFrame<CH>, t: u8) -> Result<(), E> {
+        self.write_frame(&from.crossfade(to, t))
+    }
+
+    /// Set all outputs to `state` when the returned driver is dropped
+    ///
+    /// *Note: Errors during the final write are ignored*
+    #[cfg(feature = "drop-safe")]
+    pub fn with_drop_safe(self, state: LedOut) -> DropSafe<Self> {
+        DropSafe {
+            pca: ManuallyDrop::new(self),
+            state,
+            apply: |pca, state| {
+                let _ = pca.write_all_out(state);
+            },
+        }
+    }
+}
+
+/// RGB pixels on groups of three channels
+///
+/// Pixel `n` is channels `3n + 1` (red), `3n + 2` (green) and `3n + 3` (blue), so
+/// there are `NUM_CHANNELS / 3` pixels and any remaining channels are left alone.
+/// All pixels are written in one transaction, more pixels than that are rejected
+/// with `Error::InvalidLength` before anything is written.
+#[cfg(feature = "smart-leds")]
+impl<I2C, M: Part, const CH: usize, OE, E> smart_leds_trait::SmartLedsWrite
+    for Pca963x<I2C, M, CH, OE>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    type Error = Error<E>;
+    type Color = smart_leds_trait::RGB8;
+
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Error<E>>
+    where
+        T: IntoIterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        let mut duties = [0u8; CH];
+        let mut len = 0;
+        for pixel in iterator {
+            let pixel = pixel.into();
+            let rgb = duties.get_mut(len..len + 3).ok_or(Error::InvalidLength)?;
+            rgb.copy_from_slice(&[pixel.r, pixel.g, pixel.b]);
+            len += 3;
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        self.write_duty_range(M::Channels::ALL[0], &duties[..len])
+    }
+}
+
+/// Fallible counterparts of the `Pwm` methods
+#[cfg(feature = "embedded-hal-pwm")]
+impl<I2C, M: Part, const CH: usize, OE, E> Pca963x<I2C, M, CH, OE>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    /// `Pwm::disable`, reporting bus errors
+    pub fn try_disable(&mut self, channel: M::Channels) -> Result<(), E> {
+        self.write_out(channel, LedOut::FullyOff)
+    }
+
+    /// `Pwm::enable`, reporting bus errors. Writes the `enable_mode` output mode.
+    pub fn try_enable(&mut self, channel: M::Channels) -> Result<(), E> {
+        self.write_out(channel, self.enable_mode)
+    }
+
+    /// `Pwm::set_duty`, reporting bus errors
+    pub fn try_set_duty(&mut self, channel: M::Channels, duty: u8) -> Result<(), E> {
+        self.write_duty(channel, duty)
+    }
+}
+
+/// Best effort, the 0.2 `Pwm` trait can't report errors so bus errors are
+/// dropped. Use `try_enable`, `try_disable` and `try_set_duty` to see them.
+#[cfg(feature = "embedded-hal-pwm")]
+impl<I2C, M: Part, const CH: usize, OE, E> hal::Pwm for Pca963x<I2C, M, CH, OE>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    type Channel = M::Channels;
+    type Time = ();
+    type Duty = u8;
+
+    fn disable(&mut self, channel: Self::Channel) {
+        self.try_disable(channel).unwrap_or_default()
+    }
+
+    fn enable(&mut self, channel: Self::Channel) {
+        self.try_enable(channel).unwrap_or_default()
+    }
+
+    fn get_period(&self) -> Self::Time {}
+
+    /// Last duty cycle written to `channel` through this driver
+    fn get_duty(&self, channel: Self::Channel) -> Self::Duty {
+        self.duties[channel.get_offs() as usize]
+    }
+
+    fn get_max_duty(&self) -> Self::Duty {
+        255u8
+    }
+
+    fn set_duty(&mut self, channel: Self::Channel, duty: Self::Duty) {
+        self.try_set_duty(channel, duty).unwrap_or_default()
+    }
+
+    fn set_period<P>(&mut self, _period: P)
+    where
+        P: Into<Self::Time>,
+    {
+    }
+}
+
+impl<'a, I2C, M: Part, const CH: usize, OE, E> PcaChannel<'a, Pca963x<I2C, M, CH, OE>>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    /// Write the channel pwm, see [`PCA963X::write_duty`]
+    pub fn set_duty(&mut self, value: u8) -> Result<(), E> {
+        let linked = self.pca.mirrors[self.offs as usize] | 1 << self.offs;
+        self.pca.write_linked(linked, value)
+    }
+
+    /// Last duty cycle written to the channel through this driver
+    pub fn duty(&self) -> u8 {
+        self.pca.duties[self.offs as usize]
+    }
+
+    /// Write the channel output mode, see [`PCA963X::write_out`]
+    pub fn set_out(&mut self, out: LedOut) -> Result<(), E> {
+        self.pca.write_out_offs(self.offs, out)
+    }
+}
+
+/// Best effort like the whole-device `Pwm` impl, bus errors are dropped
+#[cfg(feature = "embedded-hal-pwm")]
+impl<'a, I2C, M: Part, const CH: usize, OE, E> hal::PwmPin
+    for PcaChannel<'a, Pca963x<I2C, M, CH, OE>>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    type Duty = u8;
+
+    fn disable(&mut self) {
+        self.set_out(LedOut::FullyOff).unwrap_or_default()
+    }
+
+    /// Writes the device's `enable_mode` output mode
+    fn enable(&mut self) {
+        self.set_out(self.pca.enable_mode).unwrap_or_default()
+    }
+
+    /// Last duty cycle written to the channel through this driver
+    fn get_duty(&self) -> Self::Duty {
+        self.duty()
+    }
+
+    fn get_max_duty(&self) -> Self::Duty {
+        255u8
+    }
+
+    fn set_duty(&mut self, duty: Self::Duty) {
+        Self::set_duty(self, duty).unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "embedded-hal-1")]
+impl<'a, I2C, M: Part, const CH: usize, OE, E> embedded_hal_1::pwm::ErrorType
+    for PcaChannel<'a, Pca963x<I2C, M, CH, OE>>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+    E: core::fmt::Debug,
+{
+    type Error = Error<E>;
+}
+
+#[cfg(feature = "embedded-hal-1")]
+impl<'a, I2C, M: Part, const CH: usize, OE, E> embedded_hal_1::pwm::SetDutyCycle
+    for PcaChannel<'a, Pca963x<I2C, M, CH, OE>>
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+    E: core::fmt::Debug,
+{
+    fn max_duty_cycle(&self) -> u16 {
+        255
+    }
+
+    /// Write the channel pwm, values above `max_duty_cycle` saturate to 255
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        let duty = u8::try_from(duty).unwrap_or(u8::MAX);
+        Ok(self.set_duty(duty)?)
+    }
+}
+
+/// PCA9633 driver, 4 channels, see [`part::PCA9633`]
+pub type PCA9633<I2C, OE = ()> = Pca963x<I2C, part::PCA9633, 4, OE>;
+
+/// PCA9634 driver, 8 channels, see [`part::PCA9634`]
+pub type PCA9634<I2C, OE = ()> = Pca963x<I2C, part::PCA9634, 8, OE>;
+
+/// PCA9635 driver, 16 channels, see [`part::PCA9635`]
+pub type PCA9635<I2C, OE = ()> = Pca963x<I2C, part::PCA9635, 16, OE>;
+
+/// PCA9624 driver, 8 open-drain channels, see [`part::PCA9624`]
+pub type PCA9624<I2C, OE = ()> = Pca963x<I2C, part::PCA9624, 8, OE>;
+
+#[cfg(test)]
+mod device_tests {
+    extern crate std;
+
+    use super::*;
+    use crate::mock::{BusError, FlakyI2c};
+    use embedded_hal_mock::delay::MockNoop;
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+    use std::vec;
+
+    #[test]
+    fn test_new_config_error() {
+        // The MODE1/MODE2 write is the only transaction, fail it
+        let i2c = FlakyI2c::new(0);
+        let attempts = i2c.attempts();
+        let result = PCA9633::new_config(i2c, Address::_8Pin, Config::new());
+        assert_eq!(result.err(), Some(BusError));
+        assert_eq!(attempts.get(), 1);
+
+        // bring_up stops at the failed LEDOUT write, the duties are not zeroed
+        let i2c = FlakyI2c::new(1);
+        let attempts = i2c.attempts();
+        let mut pca = PCA9633::new(i2c, Address::_8Pin);
+        let result = pca.bring_up(
+            false,
+            Config::new(),
+            &[LedOut::Pwm; 4],
+            &mut MockNoop::new(),
+        );
+        assert_eq!(result, Err(Error::I2c(BusError)));
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[test]
+    fn test_generic_device() {
+        let expectations = [Transaction::write(
+            0x62,
+            vec![AUTOINCR_ALL | 0x02, 1, 2, 3, 4],
+        )];
+        let i2c = I2cMock::new(&expectations);
+        let mut pca: Pca963x<_, part::PCA9633, 4> = Pca963x::new(i2c, Address::_8Pin);
+        pca.write_all_duty(&[1, 2, 3, 4]).unwrap();
+        let pca: PCA9633<_> = pca;
+        assert_eq!(<PCA9633<I2cMock> as PCA963X<_, _>>::NUM_CHANNELS, 4);
+        pca.destroy().done();
+    }
+
+    #[test]
+    fn test_recommended_config() {
+        let mut i2c = I2cMock::new(&[]);
+        let pca9633 = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let config = Config::with_defaults_for(&pca9633);
+        assert_eq!(config.mode1.bits, 0b0000_0001);
+        assert_eq!(config.mode2.bits, 0b0000_0101);
+        let pca9634 = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        let config = Config::with_defaults_for(&pca9634);
+        assert_eq!(config.mode1.bits, 0b0000_0001);
+        assert_eq!(config.mode2.bits, 0b0000_0010);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_config() {
+        let expectations = [
+            Transaction::write(0x62, vec![AUTOINCR_ALL, 0x01, 0x05]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL, 0xFF, 0xC0]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_config(Config::new()).unwrap();
+        pca.write_modes_raw(0xFF, 0xC0).unwrap();
+        i2c.done();
+    }
+
+    fn bring_up_expectations(mode1: u8) -> [Transaction; 4] {
+        [
+            Transaction::write(0x15, vec![AUTOINCR_ALL, 0x01, 0x05]),
+            Transaction::write(0x15, vec![AUTOINCR_ALL | 0x0C, 0b1110_0100, 0b0000_1010]),
+            Transaction::write(0x15, vec![AUTOINCR_ALL | 0x02, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Transaction::write_read(0x15, vec![AUTOINCR_ALL], vec![mode1, 0x05]),
+        ]
+    }
+
+    #[test]
+    fn test_bring_up() {
+        let outputs = [
+            LedOut::FullyOff,
+            LedOut::FullyOn,
+            LedOut::Pwm,
+            LedOut::PwmGroup,
+            LedOut::Pwm,
+            LedOut::Pwm,
+            LedOut::FullyOff,
+            LedOut::FullyOff,
+        ];
+
+        // Auto-increment flags read back in MODE1 are ignored
+        let expectations = bring_up_expectations(0x81);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.bring_up(false, Config::default(), &outputs, &mut MockNoop::new())
+            .unwrap();
+        i2c.done();
+
+        // Power-on values first
+        let mut reset = vec![AUTOINCR_ALL, 0x11, 0x05];
+        reset.extend_from_slice(&[0; 8]);
+        reset.extend_from_slice(&[0xFF, 0, 0, 0, 0xE2, 0xE4, 0xE8, 0xE0]);
+        let mut expectations = vec![Transaction::write(0x15, reset)];
+        expectations.extend_from_slice(&bring_up_expectations(0x01));
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.bring_up(true, Config::default(), &outputs, &mut MockNoop::new())
+            .unwrap();
+        i2c.done();
+
+        // Still sleeping
+        let expectations = bring_up_expectations(0x11);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        assert!(matches!(
+            pca.bring_up(false, Config::default(), &outputs, &mut MockNoop::new()),
+            Err(Error::VerificationFailed)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_config() {
+        let expectations = [Transaction::write_read(
+            0x62,
+            vec![AUTOINCR_ALL],
+            vec![0x89, 0x25],
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let config = pca.read_config().unwrap();
+        assert_eq!(config.mode1.bits, 0x09);
+        assert_eq!(config.mode2.bits, 0x25);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_sleep_wake() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![0x00], vec![0x09]),
+            Transaction::write(0x62, vec![0x00, 0x19]),
+            Transaction::write_read(0x62, vec![0x00], vec![0x19]),
+            Transaction::write(0x62, vec![0x00, 0x09]),
+            Transaction::write_read(0x62, vec![0x00], vec![0x11]),
+            Transaction::write(0x62, vec![0x00, 0x01]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.sleep().unwrap();
+        pca.wake().unwrap();
+        pca.wake_with_delay(&mut MockNoop::new()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_probe() {
+        let mut pca = PCA9633::new(FlakyI2c::new(1), Address::_8Pin);
+        assert!(pca.probe());
+        assert!(!pca.probe());
+    }
+
+    #[test]
+    fn test_is_awake() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![0x00], vec![0x11]),
+            Transaction::write_read(0x62, vec![0x00], vec![0x01]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        assert!(!pca.is_awake().unwrap());
+        assert!(pca.is_awake().unwrap());
+        assert!(PCA9633::new(FlakyI2c::new(0), Address::_8Pin)
+            .is_awake()
+            .is_err());
+        i2c.done();
+    }
+
+    #[test]
+    fn test_begin_wake() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![0x00], vec![0x11]),
+            Transaction::write(0x62, vec![0x00, 0x01]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let token = pca.begin_wake(u32::MAX - 99).unwrap();
+        assert_eq!(token.ready_at(), 400);
+        assert!(!token.is_ready(u32::MAX));
+        assert!(!token.is_ready(399));
+        assert!(token.is_ready(400));
+        assert!(token.is_ready(10_000));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_software_reset() {
+        let expectations = [Transaction::write(0x03, vec![0xA5, 0x5A])];
+        let mut i2c = I2cMock::new(&expectations);
+        software_reset(&mut i2c).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_scan() {
+        struct Bus;
+        impl i2c::WriteRead for Bus {
+            type Error = ();
+            fn write_read(&mut self, address: u8, _: &[u8], _: &mut [u8]) -> Result<(), ()> {
+                match address {
+                    0x15 | 0x62 | 0x63 => Ok(()),
+                    _ => Err(()),
+                }
+            }
+        }
+        let mut bus = Bus;
+        let mut found = scan(&mut bus, 0x60..=0x6F);
+        assert_eq!(found.next(), Some(0x62));
+        assert_eq!(found.next(), Some(0x63));
+        assert_eq!(found.next(), None);
+    }
+
+    #[test]
+    fn test_duty_percent() {
+        assert_eq!(duty_from_percent(0), 0);
+        assert_eq!(duty_from_percent(1), 3);
+        assert_eq!(duty_from_percent(50), 128);
+        assert_eq!(duty_from_percent(100), 255);
+        assert_eq!(duty_from_percent(200), 255);
+        assert_eq!(duty_from_fraction(0.0), 0);
+        assert_eq!(duty_from_fraction(0.5), 128);
+        assert_eq!(duty_from_fraction(1.0), 255);
+        assert_eq!(duty_from_fraction(-1.0), 0);
+        assert_eq!(duty_from_fraction(2.0), 255);
+        assert_eq!(duty_from_fraction(f32::NAN), 0);
+
+        let expectations = [
+            Transaction::write(0x62, vec![0x02, 128]),
+            Transaction::write(0x62, vec![0x03, 64]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_duty_percent(Channels4::_1, 50).unwrap();
+        pca.write_duty_fraction(Channels4::_2, 0.25).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_fade_duty() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x02, 30]),
+            Transaction::write(0x62, vec![0x02, 20]),
+            Transaction::write(0x62, vec![0x02, 10]),
+            Transaction::write(0x62, vec![0x02, 0]),
+            Transaction::write(0x62, vec![0x03, 99]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let delay = &mut MockNoop::new();
+        pca.fade_duty(Channels4::_1, 30, 0, 3, 1000, delay).unwrap();
+        pca.fade_duty(Channels4::_1, 7, 7, 3, 1000, delay).unwrap();
+        pca.fade_duty(Channels4::_2, 0, 99, 0, 1000, delay).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_breathe_group() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x06, 10]),
+            Transaction::write(0x62, vec![0x06, 55]),
+            Transaction::write(0x62, vec![0x06, 100]),
+            Transaction::write(0x62, vec![0x06, 55]),
+            Transaction::write(0x62, vec![0x06, 10]),
+            Transaction::write(0x62, vec![0x06, 10]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let delay = &mut MockNoop::new();
+        pca.breathe_group(10, 100, 2, 1000, delay).unwrap();
+        pca.breathe_group(10, 100, 0, 1000, delay).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_uniform_duty() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x82, 0x80, 0x80, 0x80, 0x80]),
+            Transaction::write(0x15, vec![0x82, 7, 7, 7, 7, 7, 7, 7, 7]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        PCA9633::new(i2c.clone(), Address::_8Pin)
+            .write_uniform_duty(0x80)
+            .unwrap();
+        PCA9634::new(i2c.clone(), Address::Custom(0x15))
+            .write_uniform_duty(7)
+            .unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_duty_gamma() {
+        assert_eq!(GAMMA_2_2[0], 0);
+        assert_eq!(GAMMA_2_2[128], 56);
+        assert_eq!(GAMMA_2_2[255], 255);
+        assert!(GAMMA_2_2.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut curve = [0u8; 256];
+        curve[10] = 200;
+        let expectations = [
+            Transaction::write(0x62, vec![0x02, 56]),
+            Transaction::write(0x62, vec![0x03, 200]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_duty_gamma(Channels4::_1, 128).unwrap();
+        pca.write_duty_with_curve(Channels4::_2, 10, &curve)
+            .unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_group_period() {
+        assert_eq!(group_freq_for_period_ms(0), 0);
+        assert_eq!(group_freq_for_period_ms(42), 0);
+        assert_eq!(group_freq_for_period_ms(1000), 23);
+        assert_eq!(group_freq_for_period_ms(1020), 23);
+        assert_eq!(group_freq_for_period_ms(1030), 24);
+        assert_eq!(group_freq_for_period_ms(10667), 255);
+        assert_eq!(group_freq_for_period_ms(u16::MAX), 255);
+
+        assert_eq!(period_ms_for_group_freq(0), 42);
+        assert_eq!(period_ms_for_group_freq(11), 500);
+        assert_eq!(period_ms_for_group_freq(23), 1000);
+        assert_eq!(period_ms_for_group_freq(255), 10667);
+
+        let expectations = [
+            Transaction::write(0x62, vec![0x07, 11]),
+            Transaction::write_read(0x62, vec![0x07], vec![11]),
+            Transaction::write_read(0x62, vec![0x06], vec![64]),
+            Transaction::write_read(0x62, vec![0x07], vec![255]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_group_period_ms(500).unwrap();
+        assert_eq!(pca.read_group_period_ms().unwrap(), 500);
+        assert_eq!(pca.read_group_duty().unwrap(), 64);
+        assert_eq!(pca.read_group_freq().unwrap(), 255);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_group_period_round_trip() {
+        for freq in 0..=255u8 {
+            let ms = period_ms_for_group_freq(freq);
+            assert_eq!(group_freq_for_period_ms(ms), freq);
+        }
+        // In range the decoded period is the nearest step, a step is 41.7ms
+        for ms in 42..=10667u16 {
+            let decoded = period_ms_for_group_freq(group_freq_for_period_ms(ms));
+            assert!(decoded.abs_diff(ms) <= 21, "{}ms", ms);
+        }
+        // Out of range, clamped to the first and last step
+        let clamped = |ms| period_ms_for_group_freq(group_freq_for_period_ms(ms));
+        assert_eq!(clamped(1), 42);
+        assert_eq!(clamped(60000), 10667);
+    }
+
+    #[test]
+    fn test_configure_blink() {
+        let expectations = [
+            Transaction::write_read(0x15, vec![0x01], vec![0b0000_0101]),
+            Transaction::write(0x15, vec![0x01, 0b0010_0101]),
+            Transaction::write(0x15, vec![AUTOINCR_ALL | 0x0A, 64, 23]),
+            Transaction::write_read(0x15, vec![0x01], vec![0b0010_0101]),
+            Transaction::write(0x15, vec![0x01, 0b0000_0101]),
+            Transaction::write(0x15, vec![0x0A, 200]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.configure_blink(1000, 64).unwrap();
+        pca.configure_dimming(200).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_register() {
+        type Pca9633 = PCA9633<I2cMock<'static>>;
+        type Pca9634 = PCA9634<I2cMock<'static>>;
+        assert_eq!(Pca9633::register(Register::Pwm(3)), Some(0x05));
+        assert_eq!(Pca9633::register(Register::Pwm(4)), None);
+        assert_eq!(Pca9633::register(Register::LedOut(0)), Some(0x08));
+        assert_eq!(Pca9633::register(Register::LedOut(1)), None);
+        assert_eq!(Pca9634::register(Register::LedOut(1)), Some(0x0D));
+        assert_eq!(Pca9634::register(Register::GrpFreq), Some(0x0B));
+        assert_eq!(Pca9634::register(Register::SubAddr(0)), None);
+        assert_eq!(Pca9634::register(Register::SubAddr(3)), Some(0x10));
+        assert_eq!(Pca9634::register(Register::AllCallAddr), Some(0x11));
+
+        let expectations = [
+            Transaction::write(0x15, vec![0x09, 0x42]),
+            Transaction::write_read(0x15, vec![0x0D], vec![0xAA]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.write_reg(Register::Pwm(7), 0x42).unwrap();
+        assert_eq!(pca.read_reg(Register::LedOut(1)).unwrap(), 0xAA);
+        assert!(matches!(
+            pca.write_reg(Register::Pwm(8), 0),
+            Err(Error::OutOfRange)
+        ));
+        i2c.done();
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_register_name() {
+        type Pca9635 = PCA9635<I2cMock<'static>>;
+        for offset in 0..=Pca9635::ALLCALLADR {
+            let reg = Pca9635::register_name(offset).unwrap();
+            assert_eq!(Pca9635::register(reg), Some(offset));
+        }
+        assert_eq!(PCA9633::<I2cMock<'static>>::register_name(0x0D), None);
+        assert_eq!(Pca9635::register_name(AUTOINCR_ALL | 0x02), None);
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_trace_bulk() {
+        let i2c = FlakyI2c::new(usize::MAX);
+        let attempts = i2c.attempts();
+        let mut pca = PCA9634::new(i2c, Address::Custom(0x15));
+        let traced = crate::mock::traced();
+
+        pca.write_modes_raw(0x01, 0x05).unwrap();
+        pca.write_config(Config::new()).unwrap();
+        pca.read_config().unwrap();
+        pca.write_all_duty(&[1; 8]).unwrap();
+        pca.read_all_duty(&mut [0; 8]).unwrap();
+        pca.write_duty_range(Channels8::_3, &[1, 2]).unwrap();
+        pca.write_all_out(LedOut::Pwm).unwrap();
+        pca.write_all_out_array(&[LedOut::Pwm; 8]).unwrap();
+        pca.read_all_out().unwrap();
+        pca.mirror(Channels8::_1, &[Channels8::_4]);
+        pca.write_duty(Channels8::_1, 7).unwrap();
+        pca.configure_blink(1000, 128).unwrap();
+        pca.reset_to_defaults().unwrap();
+        pca.write_registers(0x02, AutoIncrement::All, &[1, 2])
+            .unwrap();
+        pca.read_registers(0x02, AutoIncrement::All, &mut [0; 2])
+            .unwrap();
+        pca.write_frame(&DutyFrame::uniform(3)).unwrap();
+        pca.read_frame().unwrap();
+        pca.sync_cache().unwrap();
+
+        assert_eq!(crate::mock::traced() - traced, attempts.get());
+    }
+
+    #[test]
+    fn test_registers() {
+        let expectations = [
+            Transaction::write(0x15, vec![0x02, 1, 2]),
+            Transaction::write(0x15, vec![0x82, 1, 2]),
+            Transaction::write(0x15, vec![0xA8, 1, 2, 3]),
+            Transaction::write(0x15, vec![0xCA, 0x80, 0x17]),
+            Transaction::write_read(0x15, vec![0xE2], vec![1, 2, 3]),
+            Transaction::write_read(0x15, vec![0x11], vec![0xE0]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.write_registers(0x02, AutoIncrement::None, &[1, 2])
+            .unwrap();
+        pca.write_registers(0x02, AutoIncrement::All, &[1, 2])
+            .unwrap();
+        pca.write_registers(0x08, AutoIncrement::Brightness, &[1, 2, 3])
+            .unwrap();
+        pca.write_registers(0x0A, AutoIncrement::Global, &[0x80, 0x17])
+            .unwrap();
+        let mut buf = [0u8; 3];
+        pca.read_registers(0x02, AutoIncrement::BrightnessGlobal, &mut buf)
+            .unwrap();
+        assert_eq!(buf, [1, 2, 3]);
+        let mut buf = [0u8; 1];
+        pca.read_registers(0x11, AutoIncrement::None, &mut buf)
+            .unwrap();
+        assert_eq!(buf, [0xE0]);
+        assert!(matches!(
+            pca.write_registers(0x12, AutoIncrement::None, &[0]),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            pca.write_registers(0x02, AutoIncrement::All, &[0; MAX_REGISTER_SPAN + 1]),
+            Err(Error::InvalidLength)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_configure_subaddress() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x0B, 0xE8]),
+            Transaction::write_read(0x62, vec![0x00], vec![0x01]),
+            Transaction::write(0x62, vec![0x00, 0x03]),
+            Transaction::write(0x62, vec![0x09, 0xE4]),
+            Transaction::write_read(0x62, vec![0x00], vec![0x0D]),
+            Transaction::write(0x62, vec![0x00, 0x05]),
+            Transaction::write_read(0x62, vec![0x0B], vec![0xE8]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.configure_subaddress(3, 0x74, true).unwrap();
+        pca.configure_subaddress(1, 0x72, false).unwrap();
+        assert_eq!(pca.read_sub_address(3).unwrap(), 0x74);
+        assert!(matches!(
+            pca.configure_subaddress(4, 0x10, true),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            pca.configure_subaddress(2, 0xE4, true),
+            Err(Error::InvalidAddress)
+        ));
+        assert!(matches!(pca.read_sub_address(0), Err(Error::OutOfRange)));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_new_awake() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x80, 0x01, 0x05]),
+            Transaction::write(0x15, vec![0x80, 0x01, 0x01]),
+            // Open-drain with OUTNE = 10, not the power-on MODE2 of 0x05
+            Transaction::write(0x16, vec![0x80, 0x01, 0x02]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        PCA9633::new_awake(i2c.clone(), Address::_8Pin, &mut MockNoop::new()).unwrap();
+        PCA9624::new_awake(i2c.clone(), Address::Custom(0x15), &mut MockNoop::new()).unwrap();
+        PCA9634::new_awake(i2c.clone(), Address::Custom(0x16), &mut MockNoop::new()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_new_config_awake() {
+        let expectations = [Transaction::write(0x62, vec![0x80, 0x01, 0x15])];
+        let mut i2c = I2cMock::new(&expectations);
+        let conf = Config::default().invert(true);
+        let mut delay = MockNoop::new();
+        PCA9633::new_config_awake(i2c.clone(), Address::_8Pin, conf, &mut delay).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_address() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x02, 0x10]),
+            Transaction::write(0x70, vec![0x02, 0x20]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        assert_eq!(pca.address(), 0x62);
+        pca.write_duty(Channels4::_1, 0x10).unwrap();
+        pca.set_address(Address::Custom(0x70));
+        assert_eq!(pca.address(), 0x70);
+        pca.write_duty(Channels4::_1, 0x20).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_channel() {
+        let expectations = [Transaction::write(0x62, vec![0x04, 0x33])];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let mut ch = pca.channel(Channels4::_3);
+        assert_eq!(ch.offset(), 2);
+        ch.set_duty(0x33).unwrap();
+        assert_eq!(ch.duty(), 0x33);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_output_enable() {
+        struct Pin(bool);
+
+        impl OutputPin for Pin {
+            type Error = core::convert::Infallible;
+
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                self.0 = false;
+                Ok(())
+            }
+
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                self.0 = true;
+                Ok(())
+            }
+        }
+
+        let expectations = [Transaction::write(0x62, vec![0x02, 0x10])];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new_with_oe(i2c.clone(), Address::_8Pin, Pin(true));
+        pca.enable_outputs().unwrap();
+        assert!(!pca.oe.0);
+        pca.write_duty(Channels4::_1, 0x10).unwrap();
+        pca.disable_outputs().unwrap();
+        let (_, oe) = pca.destroy_with_oe();
+        assert!(oe.0);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_reset_to_defaults() {
+        let expectations = [
+            Transaction::write(
+                0x62,
+                vec![
+                    0x80, 0x11, 0x05, 0, 0, 0, 0, 0xFF, 0x00, 0x00, 0xE2, 0xE4, 0xE8, 0xE0,
+                ],
+            ),
+            Transaction::write(
+                0x15,
+                vec![
+                    0x80, 0x11, 0x05, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0x00, 0x00, 0x00, 0xE2, 0xE4,
+                    0xE8, 0xE0,
+                ],
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.reset_to_defaults().unwrap();
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.reset_to_defaults().unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pwm_register() {
+        assert_eq!(pwm_register(0x02, 3, 4), 0x05);
+        assert_eq!(pwm_register(0x02, 15, 16), 0x11);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "channel offset beyond the PWM registers")]
+    fn test_pwm_register_out_of_range() {
+        // A Channels8 offset on the four channel PCA9633 would hit GRPPWM (0x06)
+        pwm_register(0x02, Channels8::_5.get_offs(), 4);
+    }
+
+    #[test]
+    fn test_pwm_span() {
+        type Pca9633 = PCA9633<I2cMock<'static>>;
+        type Pca9634 = PCA9634<I2cMock<'static>>;
+        assert_eq!(<Pca9633 as PCA963X<_, _>>::PWM0 + 4, 0x06);
+        assert_eq!(<Pca9633 as PCA963X<_, _>>::GRPPWM, 0x06);
+        assert_eq!(<Pca9634 as PCA963X<_, _>>::PWM0 + 8, 0x0A);
+        assert_eq!(<Pca9634 as PCA963X<_, _>>::GRPPWM, 0x0A);
+        assert!(pwm_span_ok(0x02, 4, 0x06));
+        assert!(pwm_span_ok(0x02, 8, 0x0A));
+        assert!(!pwm_span_ok(0x02, 8, 0x06));
+        assert!(!pwm_span_ok(0x02, 4, 0x0A));
+    }
+
+    #[test]
+    fn test_pca9633_sub_addresses() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x09, 0x71 << 1]),
+            Transaction::write(0x62, vec![0x0A, 0x72 << 1]),
+            Transaction::write(0x62, vec![0x0B, 0x74 << 1]),
+            Transaction::write(0x62, vec![0x0C, 0x70 << 1]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_sub_address1(0x71).unwrap();
+        pca.write_sub_address2(0x72).unwrap();
+        pca.write_sub_address3(0x74).unwrap();
+        pca.write_all_call_address1(0x70).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pca9634_sub_addresses() {
+        let expectations = [
+            Transaction::write(0x15, vec![0x0E, 0x71 << 1]),
+            Transaction::write(0x15, vec![0x0F, 0x72 << 1]),
+            Transaction::write(0x15, vec![0x10, 0x74 << 1]),
+            Transaction::write(0x15, vec![0x11, 0x70 << 1]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.write_sub_address1(0x71).unwrap();
+        pca.write_sub_address2(0x72).unwrap();
+        pca.write_sub_address3(0x74).unwrap();
+        pca.write_all_call_address1(0x70).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pca9635_config_round_trip() {
+        let config = Config::new().sleep(false).blink(true).sub2(true);
+        let (mode1, mode2) = (config.mode1.bits, config.mode2.bits);
+        let expectations = [
+            Transaction::write(0x40, vec![AUTOINCR_ALL, mode1, mode2]),
+            Transaction::write_read(0x40, vec![AUTOINCR_ALL], vec![mode1, mode2]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9635::new(i2c.clone(), Address::Custom(0x40));
+        pca.write_config(config).unwrap();
+        let read = pca.read_config().unwrap();
+        assert_eq!((read.mode1, read.mode2), (config.mode1, config.mode2));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pca9624_rejects_totem_pole() {
+        let expectations = [Transaction::write(0x15, vec![AUTOINCR_ALL, 0x01, 0x01])];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9624::new(i2c.clone(), Address::Custom(0x15));
+        let totem_pole = Config::new().out_drv(OutDrv::TotemPole);
+        assert!(!PCA9624::<I2cMock>::supports_config(&totem_pole));
+        assert!(PCA9634::<I2cMock>::supports_config(&totem_pole));
+        assert!(matches!(
+            pca.write_config_checked(totem_pole),
+            Err(Error::UnsupportedConfig)
+        ));
+        assert!(matches!(
+            pca.bring_up(false, totem_pole, &[LedOut::Pwm; 8], &mut MockNoop::new()),
+            Err(Error::UnsupportedConfig)
+        ));
+        pca.write_config_checked(Config::with_defaults_for(&pca))
+            .unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pca9635_registers() {
+        type Pca9635 = PCA9635<I2cMock<'static>>;
+        assert_eq!(<Pca9635 as PCA963X<_, _>>::NUM_LEDOUT, 4);
+        let expectations = [
+            Transaction::write(0x40, vec![0x11, 0x80]),
+            Transaction::write(0x40, vec![0x17, 0b1100_0000]),
+            Transaction::write(0x40, vec![AUTOINCR_ALL | 0x14, 0x55, 0x55, 0x55, 0x55]),
+            Transaction::write(0x40, vec![0x1B, 0x70 << 1]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9635::new(i2c.clone(), Address::Custom(0x40));
+        pca.write_duty(Channels16::_16, 0x80).unwrap();
+        pca.write_out(Channels16::_16, LedOut::PwmGroup).unwrap();
+        pca.write_all_out(LedOut::FullyOn).unwrap();
+        pca.write_all_call_address1(0x70).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_out_preserves_channels() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![AUTOINCR_ALL | 0x08], vec![0b1110_0100]),
+            Transaction::write(0x62, vec![0x08, 0b1101_0100]),
+            Transaction::write(0x62, vec![0x08, 0b1101_1000]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.sync_cache().unwrap();
+        pca.write_out(Channels4::_3, LedOut::FullyOn).unwrap();
+        pca.write_out(Channels4::_2, LedOut::Pwm).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_wake_and_resume() {
+        let expectations = [
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 10, 20, 30, 40]),
+            Transaction::write(0x62, vec![0x08, 0b0000_0010]),
+            Transaction::write_read(0x62, vec![0x00], vec![0x11]),
+            Transaction::write(0x62, vec![0x00, 0x01]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x08, 0b0000_0010]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 10, 20, 30, 40]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_frame(&DutyFrame([10, 20, 30, 40])).unwrap();
+        pca.write_out(Channels4::_1, LedOut::Pwm).unwrap();
+        pca.wake_and_resume().unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_num_channels() {
+        fn offsets<D: PCA963X<FlakyI2c, BusError>>(pca: &D) -> u8 {
+            (0..pca.num_channels())
+                .filter_map(D::Channels::from_index)
+                .map(|ch| ch.get_offs())
+                .sum()
+        }
+        let i2c = FlakyI2c::new(0);
+        assert_eq!(PCA9633::new(i2c, Address::_8Pin).num_channels(), 4);
+        assert_eq!(offsets(&PCA9634::new_raw(FlakyI2c::new(0), 0x15)), 28);
+    }
+
+    #[test]
+    fn test_new_raw() {
+        let pca = PCA9634::new_raw(FlakyI2c::new(0), 0x15);
+        assert_eq!(pca.address(), 0x15);
+    }
+
+    #[test]
+    fn test_read_write() {
+        let expectations = [
+            Transaction::write(0x15, vec![0x0A, 0x33]),
+            Transaction::write_read(0x15, vec![0x0A], vec![0x33]),
+            Transaction::write(0x15, vec![0x0C, 0b0010_0000]),
+            Transaction::write(0x15, vec![0x0D, 0b0000_0011]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca.write(0x0A, 0x33).unwrap();
+        assert_eq!(pca.read(0x0A).unwrap(), 0x33);
+        pca.write_out(Channels8::_3, LedOut::Pwm).unwrap();
+        pca.write_out(Channels8::_5, LedOut::PwmGroup).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_disable_all_call() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![0x00], vec![0x0F]),
+            Transaction::write(0x62, vec![0x00, 0x0E]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.disable_all_call().unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_update_modes() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![0x00], vec![0x91]),
+            Transaction::write(0x62, vec![0x00, 0x0A]),
+            Transaction::write_read(0x62, vec![0x01], vec![0x05]),
+            Transaction::write(0x62, vec![0x01, 0x25]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let (clear, set) = (Mode1::Sleep | Mode1::AllCall, Mode1::Sub1 | Mode1::Sub3);
+        pca.update_mode1(|mode1| (mode1 - clear) | set).unwrap();
+        pca.update_mode2(|mode2| mode2 | Mode2::DmBlink).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_mode2_flags() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![0x01], vec![0x05]),
+            Transaction::write(0x62, vec![0x01, 0x15]),
+            Transaction::write_read(0x62, vec![0x01], vec![0x15]),
+            Transaction::write(0x62, vec![0x01, 0x1D]),
+            Transaction::write_read(0x62, vec![0x01], vec![0x1D]),
+            Transaction::write(0x62, vec![0x01, 0x19]),
+            Transaction::write_read(0x62, vec![0x01], vec![0x19]),
+            Transaction::write(0x62, vec![0x01, 0x1A]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.set_invert(true).unwrap();
+        pca.set_och(Och::ChangeOnAck).unwrap();
+        pca.set_out_drv(OutDrv::OpenDrain).unwrap();
+        pca.set_outne(OutputDrive::OutNe10).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_out() {
+        let expectations = [
+            Transaction::write_read(0x15, vec![0x0D], vec![0b1110_0100]),
+            Transaction::write_read(0x15, vec![0x0D], vec![0b1110_0100]),
+            Transaction::write_read(0x15, vec![0x0D], vec![0b1110_0100]),
+            Transaction::write_read(0x15, vec![0x0D], vec![0b1110_0100]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        assert!(matches!(pca.read_out(Channels8::_5), Ok(LedOut::FullyOff)));
+        assert!(matches!(pca.read_out(Channels8::_6), Ok(LedOut::FullyOn)));
+        assert!(matches!(pca.read_out(Channels8::_7), Ok(LedOut::Pwm)));
+        assert!(matches!(pca.read_out(Channels8::_8), Ok(LedOut::PwmGroup)));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_all_duty() {
+        let expectations = [
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 1, 2, 3, 4]),
+            Transaction::write(0x15, vec![AUTOINCR_ALL | 0x02, 1, 2, 3, 4, 5, 6, 7, 8]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca9633 = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca9633.write_all_duty(&[1, 2, 3, 4]).unwrap();
+        assert!(matches!(
+            pca9633.write_all_duty(&[1, 2, 3]),
+            Err(Error::InvalidLength)
+        ));
+        assert!(matches!(
+            pca9633.write_all_duty(&[1, 2, 3, 4, 5]),
+            Err(Error::InvalidLength)
+        ));
+        let mut pca9634 = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca9634.write_all_duty(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_duty_range() {
+        let expectations = [Transaction::write(
+            0x62,
+            vec![AUTOINCR_BRIGHT | 0x03, 1, 255 - 2, 3],
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.set_channel_inverted(Channels4::_3, true);
+        pca.write_duty_range(Channels4::_2, &[1, 2, 3]).unwrap();
+        assert!(matches!(
+            pca.write_duty_range(Channels4::_3, &[1, 2, 3]),
+            Err(Error::InvalidLength)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_all_duty() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![AUTOINCR_ALL | 0x02], vec![1, 2, 3, 4]),
+            Transaction::write_read(
+                0x15,
+                vec![AUTOINCR_ALL | 0x02],
+                vec![1, 2, 3, 4, 5, 6, 7, 8],
+            ),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca9633 = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let mut duties = [0u8; PCA9633::<I2cMock>::NUM_CHANNELS as usize];
+        pca9633.read_all_duty(&mut duties).unwrap();
+        assert_eq!(duties, [1, 2, 3, 4]);
+        assert!(matches!(
+            pca9633.read_all_duty(&mut [0u8; 8]),
+            Err(Error::InvalidLength)
+        ));
+        let mut pca9634 = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        let mut duties = [0u8; PCA9634::<I2cMock>::NUM_CHANNELS as usize];
+        pca9634.read_all_duty(&mut duties).unwrap();
+        assert_eq!(duties, [1, 2, 3, 4, 5, 6, 7, 8]);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_all_out() {
+        let expectations = [
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x08, 0b0000_0000]),
+            Transaction::write(0x15, vec![AUTOINCR_ALL | 0x0C, 0b1010_1010, 0b1010_1010]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca9633 = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca9633.write_all_out(LedOut::FullyOff).unwrap();
+        let mut pca9634 = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        pca9634.write_all_out(LedOut::Pwm).unwrap();
+        assert_eq!(PCA9633::<I2cMock>::NUM_LEDOUT, 1);
+        assert_eq!(PCA9634::<I2cMock>::NUM_LEDOUT, 2);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_duty_checked() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x08, 0b0000_1000]),
+            Transaction::write(0x62, vec![0x03, 0x40]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        assert!(matches!(
+            pca.write_duty_checked(Channels4::_2, 0x40),
+            Err(Error::NotPwm(LedOut::FullyOff))
+        ));
+        pca.write_out(Channels4::_2, LedOut::Pwm).unwrap();
+        pca.write_duty_checked(Channels4::_2, 0x40).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_staggered_enable() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x08, 0b0000_0001]),
+            Transaction::write(0x62, vec![0x08, 0b0000_1001]),
+            Transaction::write(0x62, vec![0x08, 0b0000_1001]),
+            Transaction::write(0x62, vec![0x08, 0b1100_1001]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let modes = [
+            LedOut::FullyOn,
+            LedOut::Pwm,
+            LedOut::FullyOff,
+            LedOut::PwmGroup,
+        ];
+        let mut delay = MockNoop::new();
+        pca.staggered_enable(&modes, 100, &mut delay).unwrap();
+        assert!(matches!(
+            pca.staggered_enable(&modes[..2], 100, &mut delay),
+            Err(Error::InvalidLength)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_all_out_array() {
+        let expectations = [Transaction::write(
+            0x15,
+            vec![AUTOINCR_ALL | 0x0C, 0b1110_0100, 0b0000_1111],
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        let mut modes = [LedOut::FullyOff; 8];
+        modes[..6].copy_from_slice(&[
+            LedOut::FullyOff,
+            LedOut::FullyOn,
+            LedOut::Pwm,
+            LedOut::PwmGroup,
+            LedOut::PwmGroup,
+            LedOut::PwmGroup,
+        ]);
+        pca.write_all_out_array(&modes).unwrap();
+        assert!(matches!(
+            pca.write_all_out_array(&modes[..4]),
+            Err(Error::InvalidLength)
+        ));
+        // The cache follows, single channel writes keep the other modes
+        assert_eq!(pca.ledout_with(7, LedOut::Pwm), 0b1000_1111);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_all_out() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![AUTOINCR_ALL | 0x08], vec![0b1110_0100]),
+            Transaction::write_read(0x15, vec![AUTOINCR_ALL | 0x0C], vec![0x55, 0xAA]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca9633 = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let outs = pca9633.read_all_out().unwrap().map(|out| out as u8);
+        assert_eq!(outs, [0, 1, 2, 3]);
+        let mut pca9634 = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        let outs = pca9634.read_all_out().unwrap().map(|out| out as u8);
+        assert_eq!(outs, [1, 1, 1, 1, 2, 2, 2, 2]);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_duty16() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x03, 0xFF]),
+            Transaction::write(0x62, vec![0x04, 0x00]),
+            Transaction::write(0x62, vec![0x05, 0x12]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_duty16(Channels4::_2, 0xFFFF).unwrap();
+        pca.write_duty16(Channels4::_3, 0x0080).unwrap();
+        pca.write_duty16(Channels4::_4, 0x1234).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_mirror() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x02, 10]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x03, 20, 20]),
+            Transaction::write_read(0x62, vec![AUTOINCR_ALL | 0x02], vec![10, 20, 20, 0]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 77, 20, 20, 77]),
+            Transaction::write(0x62, vec![0x02, 5]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_duty(Channels4::_1, 10).unwrap();
+        pca.mirror(Channels4::_2, &[Channels4::_3]);
+        pca.write_duty(Channels4::_2, 20).unwrap();
+        pca.mirror(Channels4::_1, &[Channels4::_4]);
+        pca.write_duty(Channels4::_1, 77).unwrap();
+        pca.mirror(Channels4::_1, &[]);
+        pca.write_duty(Channels4::_1, 5).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_channel_inverted() {
+        let expectations = [
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 10, 255 - 20, 30, 40]),
+            Transaction::write(0x62, vec![0x03, 255 - 50]),
+            Transaction::write(0x62, vec![0x04, 50]),
+            Transaction::write_read(0x62, vec![0x03], vec![255 - 50]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 10, 20, 30, 40]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.set_channel_inverted(Channels4::_2, true);
+        pca.write_frame(&DutyFrame([10, 20, 30, 40])).unwrap();
+        pca.write_duty(Channels4::_2, 50).unwrap();
+        pca.write_duty(Channels4::_3, 50).unwrap();
+        assert_eq!(pca.read_duty(Channels4::_2).unwrap(), 50);
+        pca.set_channel_inverted(Channels4::_2, false);
+        pca.write_frame(&DutyFrame([10, 20, 30, 40])).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_cct() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x02, 200]),
+            Transaction::write(0x62, vec![0x03, 0]),
+            Transaction::write(0x62, vec![0x02, 0]),
+            Transaction::write(0x62, vec![0x03, 200]),
+            Transaction::write(0x62, vec![0x02, 127]),
+            Transaction::write(0x62, vec![0x03, 128]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.set_cct(Channels4::_1, Channels4::_2, 0, 200).unwrap();
+        pca.set_cct(Channels4::_1, Channels4::_2, 255, 200).unwrap();
+        pca.set_cct(Channels4::_1, Channels4::_2, 128, 255).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_after_block_transfer() {
+        let expectations = [
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 1, 2, 3, 4]),
+            Transaction::write_read(0x62, vec![0x08], vec![0xAA]),
+            Transaction::write_read(0x62, vec![AUTOINCR_ALL | 0x02], vec![1, 2, 3, 4]),
+            Transaction::write_read(0x62, vec![0x01], vec![0x05]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.write_frame(&DutyFrame([1, 2, 3, 4])).unwrap();
+        assert_eq!(pca.read(0x08).unwrap(), 0xAA);
+        pca.read_frame().unwrap();
+        assert_eq!(pca.read(0x01).unwrap(), 0x05);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_split_write_read() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x03]),
+            Transaction::read(0x62, vec![0x42]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(SplitWriteRead::new(i2c.clone()), Address::_8Pin);
+        assert_eq!(pca.read_duty(Channels4::_2).unwrap(), 0x42);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let expectations = [Transaction::write(
+            0x62,
+            vec![AUTOINCR_ALL | 0x02, 50, 50, 125, 150],
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let from = DutyFrame([0, 100, 200, 50]);
+        let to = DutyFrame([100, 0, 50, 250]);
+        pca.crossfade(&from, &to, 128).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_rotate_duties() {
+        let expectations = [
+            Transaction::write_read(0x62, vec![AUTOINCR_ALL | 0x02], vec![10, 20, 30, 40]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 40, 10, 20, 30]),
+            Transaction::write_read(0x62, vec![AUTOINCR_ALL | 0x02], vec![10, 20, 30, 40]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 20, 30, 40, 10]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        pca.rotate_duties(1).unwrap();
+        pca.rotate_duties(-5).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "smart-leds")]
+    fn test_smart_leds_write() {
+        use smart_leds_trait::{SmartLedsWrite, RGB8};
+
+        let expectations = [Transaction::write(
+            0x15,
+            vec![AUTOINCR_BRIGHT | 0x02, 1, 2, 3, 4, 5, 6],
+        )];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        let pixels = [RGB8::new(1, 2, 3), RGB8::new(4, 5, 6)];
+        SmartLedsWrite::write(&mut pca, pixels.iter().cloned()).unwrap();
+        assert!(matches!(
+            SmartLedsWrite::write(&mut pca, [RGB8::default(); 3].iter().cloned()),
+            Err(Error::InvalidLength)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-hal-pwm")]
+    fn test_pwm_get_duty() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x03, 200]),
+            Transaction::write(0x62, vec![AUTOINCR_ALL | 0x02, 1, 2, 3, 4]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        assert_eq!(Pwm::get_duty(&pca, Channels4::_2), 0);
+        Pwm::set_duty(&mut pca, Channels4::_2, 200);
+        assert_eq!(Pwm::get_duty(&pca, Channels4::_2), 200);
+        pca.write_frame(&DutyFrame([1, 2, 3, 4])).unwrap();
+        assert_eq!(Pwm::get_duty(&pca, Channels4::_4), 4);
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-hal-pwm")]
+    fn test_pwm_try() {
+        let mut pca = PCA9633::new(FlakyI2c::new(1), Address::_8Pin);
+        pca.try_set_duty(Channels4::_1, 10).unwrap();
+        assert_eq!(pca.try_set_duty(Channels4::_1, 20), Err(BusError));
+        assert_eq!(pca.try_enable(Channels4::_1), Err(BusError));
+        assert_eq!(pca.try_disable(Channels4::_1), Err(BusError));
+        // The infallible trait methods drop the same errors
+        Pwm::set_duty(&mut pca, Channels4::_1, 20);
+        assert_eq!(Pwm::get_duty(&pca, Channels4::_1), 10);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-hal-pwm")]
+    fn test_pwm_pin() {
+        use hal::PwmPin;
+
+        let expectations = [
+            Transaction::write(0x62, vec![0x04, 0x80]),
+            Transaction::write(0x62, vec![0x08, 0b0010_0000]),
+            Transaction::write(0x62, vec![0x08, 0b0000_0000]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let mut ch = pca.channel(Channels4::_3);
+        PwmPin::set_duty(&mut ch, 0x80);
+        assert_eq!(ch.get_duty(), 0x80);
+        assert_eq!(ch.get_max_duty(), 255);
+        ch.enable();
+        ch.disable();
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-hal-pwm")]
+    fn test_pwm_enable_mode() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x08, 0b0000_0010]),
+            Transaction::write(0x62, vec![0x08, 0b0000_1110]),
+            Transaction::write(0x62, vec![0x08, 0b0000_1100]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        assert!(matches!(pca.enable_mode(), LedOut::Pwm));
+        Pwm::enable(&mut pca, Channels4::_1);
+        pca.set_enable_mode(LedOut::PwmGroup);
+        Pwm::enable(&mut pca, Channels4::_2);
+        Pwm::disable(&mut pca, Channels4::_1);
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "drop-safe")]
+    fn test_drop_safe() {
+        let expectations = [
+            Transaction::write(0x15, vec![0x02, 0x42]),
+            Transaction::write(0x15, vec![AUTOINCR_ALL | 0x0C, 0x00, 0x00]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca =
+            PCA9634::new(i2c.clone(), Address::Custom(0x15)).with_drop_safe(LedOut::FullyOff);
+        pca.write_duty(Channels8::_1, 0x42).unwrap();
+        drop(pca);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_destroy() {
+        let mut pca = PCA9633::new(FlakyI2c::new(2), Address::_8Pin);
+        pca.write_duty(Channels4::_1, 1).unwrap();
+        assert_eq!(pca.destroy().ok, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "drop-safe")]
+    fn test_destroy_skips_drop_safe() {
+        let pca = PCA9634::new(I2cMock::new(&[]), Address::Custom(0x15));
+        let pca = pca.with_drop_safe(LedOut::FullyOff);
+        pca.into_inner().destroy().done();
+    }
+
+    #[test]
+    #[cfg(feature = "drop-safe")]
+    fn test_drop_not_safe() {
+        let mut i2c = I2cMock::new(&[]);
+        let pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        drop(pca);
+        i2c.done();
+    }
+}
@@ -0,0 +1,172 @@
+//! Test helpers
+
+extern crate std;
+
+use crate::i2c;
+use core::cell::Cell;
+#[cfg(feature = "embedded-hal-1")]
+use embedded_hal_1::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, Operation};
+use std::rc::Rc;
+
+#[cfg(feature = "trace")]
+std::thread_local! {
+    static TRACED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Count a transaction logged by `trace_register!`
+#[cfg(feature = "trace")]
+pub fn count_traced() {
+    TRACED.with(|n| n.set(n.get() + 1));
+}
+
+/// Number of transactions logged on this thread so far
+#[cfg(feature = "trace")]
+pub fn traced() -> usize {
+    TRACED.with(|n| n.get())
+}
+
+/// Bus error of [`FlakyI2c`]
+#[derive(Debug, PartialEq)]
+pub struct BusError;
+
+/// I2C bus which completes the first `ok` transactions and fails all others
+pub struct FlakyI2c {
+    pub ok: usize,
+    /// Number of transactions attempted, shared with the clones from `attempts`
+    attempts: Rc<Cell<usize>>,
+}
+
+impl FlakyI2c {
+    pub fn new(ok: usize) -> Self {
+        FlakyI2c {
+            ok,
+            attempts: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Counter of attempted transactions, which outlives the bus
+    pub fn attempts(&self) -> Rc<Cell<usize>> {
+        self.attempts.clone()
+    }
+
+    fn transaction(&mut self) -> Result<(), BusError> {
+        self.attempts.set(self.attempts.get() + 1);
+        if self.ok == 0 {
+            return Err(BusError);
+        }
+        self.ok -= 1;
+        Ok(())
+    }
+}
+
+impl i2c::Write for FlakyI2c {
+    type Error = BusError;
+
+    fn write(&mut self, _address: u8, _bytes: &[u8]) -> Result<(), Self::Error> {
+        self.transaction()
+    }
+}
+
+impl i2c::WriteRead for FlakyI2c {
+    type Error = BusError;
+
+    fn write_read(
+        &mut self,
+        _address: u8,
+        _bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        buffer.fill(0);
+        self.transaction()
+    }
+}
+
+/// Register file of a device at `address`, auto-increment flags are ignored
+#[cfg(feature = "embedded-hal-1")]
+pub struct RegisterFile {
+    pub address: u8,
+    pub regs: [u8; 0x20],
+    /// Number of transactions addressed to the device
+    pub transactions: usize,
+    pointer: usize,
+}
+
+#[cfg(feature = "embedded-hal-1")]
+impl RegisterFile {
+    pub fn new(address: u8) -> Self {
+        RegisterFile {
+            address,
+            regs: [0; 0x20],
+            transactions: 0,
+            pointer: 0,
+        }
+    }
+
+    fn transfer(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), ErrorKind> {
+        if address != self.address {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        self.transactions += 1;
+        for op in operations {
+            match op {
+                Operation::Write(bytes) => {
+                    if let Some((pointer, data)) = bytes.split_first() {
+                        self.pointer = (pointer & 0x1F) as usize;
+                        for byte in data {
+                            self.regs[self.pointer] = *byte;
+                            self.pointer += 1;
+                        }
+                    }
+                }
+                Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.regs[self.pointer];
+                        self.pointer += 1;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-hal-1")]
+impl ErrorType for RegisterFile {
+    type Error = ErrorKind;
+}
+
+#[cfg(feature = "embedded-hal-1")]
+impl embedded_hal_1::i2c::I2c for RegisterFile {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transfer(address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for RegisterFile {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transfer(address, operations)
+    }
+}
+
+/// Run `future` to completion, for futures that never wait on anything
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    use core::task::{Context, Poll, Waker};
+
+    let mut future = core::pin::pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! Register maps of the supported parts
+//!
+//! A [`Part`] describes one chip for [`Pca963x`]: where its registers are, its
+//! channel type and the config it is usually run with. The device types are
+//! aliases over the markers here, e.g. `PCA9633<I2C>` is
+//! `Pca963x<I2C, part::PCA9633, 4>`.
+//!
+//! Other parts with the same register layout can be added by implementing
+//! [`Part`] for a new marker:
+//!
+//! ```
+//! use pca963x::part::Part;
+//! use pca963x::{Address, Channels4, Config, Pca963x};
+//!
+//! struct MyPart;
+//!
+//! impl Part for MyPart {
+//!     const MODE1: u8 = 0x00;
+//!     const MODE2: u8 = 0x01;
+//!     const PWM0: u8 = 0x02;
+//!     const GRPPWM: u8 = 0x06;
+//!     const GRPFREQ: u8 = 0x07;
+//!     const LEDOUT1: u8 = 0x08;
+//!     const SUBADR1: u8 = 0x09;
+//!     const SUBADR2: u8 = 0x0A;
+//!     const SUBADR3: u8 = 0x0B;
+//!     const ALLCALLADR: u8 = 0x0C;
+//!
+//!     type Channels = Channels4;
+//!
+//!     fn recommended_config() -> Config {
+//!         Config::new()
+//!     }
+//! }
+//!
+//! # let i2c = ();
+//! let pca: Pca963x<_, MyPart, 4> = Pca963x::new(i2c, Address::_8Pin);
+//! ```
+//!
+//! The map is checked when the device type is built, e.g. PWM registers that
+//! run into GRPPWM fail to compile:
+//!
+//! ```compile_fail
+//! use pca963x::part::Part;
+//! use pca963x::{Address, Channels4, Config, Pca963x};
+//!
+//! struct Broken;
+//!
+//! impl Part for Broken {
+//!     const MODE1: u8 = 0x00;
+//!     const MODE2: u8 = 0x01;
+//!     const PWM0: u8 = 0x02;
+//!     const GRPPWM: u8 = 0x05;
+//!     const GRPFREQ: u8 = 0x07;
+//!     const LEDOUT1: u8 = 0x08;
+//!     const SUBADR1: u8 = 0x09;
+//!     const SUBADR2: u8 = 0x0A;
+//!     const SUBADR3: u8 = 0x0B;
+//!     const ALLCALLADR: u8 = 0x0C;
+//!
+//!     type Channels = Channels4;
+//!
+//!     fn recommended_config() -> Config {
+//!         Config::new()
+//!     }
+//! }
+//!
+//! # let i2c = ();
+//! let pca: Pca963x<_, Broken, 4> = Pca963x::new(i2c, Address::_8Pin);
+//! ```
+
+use crate::{Channels, Channels16, Channels4, Channels8, Config, Mode2, OutDrv, OutputDrive};
+#[cfg(doc)]
+use crate::{Pca963x, PCA963X};
+
+/// Register map, channel type and defaults of one part
+///
+/// The PWM registers of the channels start at `PWM0` and must end right before
+/// `GRPPWM`. All registers from `MODE1` to `ALLCALLADR` must be contiguous.
+pub trait Part {
+    const MODE1: u8;
+    const MODE2: u8;
+    const PWM0: u8;
+    const GRPPWM: u8;
+    const GRPFREQ: u8;
+    const LEDOUT1: u8;
+    const SUBADR1: u8;
+    const SUBADR2: u8;
+    const SUBADR3: u8;
+    const ALLCALLADR: u8;
+
+    /// Channel type, its `ALL` must list exactly the part's channels
+    type Channels: Channels + Copy;
+
+    /// Config the part is usually run with, see [`PCA963X::recommended_config`]
+    fn recommended_config() -> Config;
+
+    /// Whether the part can run `conf`, see [`PCA963X::supports_config`]
+    fn supports_config(_conf: &Config) -> bool {
+        true
+    }
+}
+
+/// PCA9633, 4 channels
+///
+/// Commonly drives RGB LEDs directly.
+#[derive(Copy, Clone, Debug)]
+pub struct PCA9633;
+
+impl Part for PCA9633 {
+    const MODE1: u8 = 0x00;
+    const MODE2: u8 = 0x01;
+    const PWM0: u8 = 0x02;
+    //PWM1 = 0x03;
+    //PWM2 = 0x04;
+    //PWM3 = 0x05;
+    const GRPPWM: u8 = 0x06;
+    const GRPFREQ: u8 = 0x07;
+    const LEDOUT1: u8 = 0x08;
+    const SUBADR1: u8 = 0x09;
+    const SUBADR2: u8 = 0x0A;
+    const SUBADR3: u8 = 0x0B;
+    const ALLCALLADR: u8 = 0x0C;
+
+    type Channels = Channels4;
+
+    fn recommended_config() -> Config {
+        Config::new()
+    }
+}
+
+/// PCA9634, 8 channels
+///
+/// Commonly drives LED strings through external drivers, the recommended config
+/// releases them when /OE is high.
+#[derive(Copy, Clone, Debug)]
+pub struct PCA9634;
+
+impl Part for PCA9634 {
+    const MODE1: u8 = 0x00;
+    const MODE2: u8 = 0x01;
+    const PWM0: u8 = 0x02;
+    //PWM1 = 0x03;
+    //PWM2 = 0x04;
+    //PWM3 = 0x05;
+    //PWM4 = 0x06;
+    //PWM5 = 0x07;
+    //PWM6 = 0x08;
+    //PWM7 = 0x09;
+    const GRPPWM: u8 = 0x0A;
+    const GRPFREQ: u8 = 0x0B;
+    const LEDOUT1: u8 = 0x0C;
+    //LEDOUT2 = 0x0D;
+    const SUBADR1: u8 = 0x0E;
+    const SUBADR2: u8 = 0x0F;
+    const SUBADR3: u8 = 0x10;
+    const ALLCALLADR: u8 = 0x11;
+
+    type Channels = Channels8;
+
+    fn recommended_config() -> Config {
+        Config::new()
+            .out_drv(OutDrv::OpenDrain)
+            .outne(OutputDrive::OutNe10)
+    }
+}
+
+/// PCA9635, 16 channels
+///
+/// For larger LED arrays and matrices.
+#[derive(Copy, Clone, Debug)]
+pub struct PCA9635;
+
+impl Part for PCA9635 {
+    const MODE1: u8 = 0x00;
+    const MODE2: u8 = 0x01;
+    const PWM0: u8 = 0x02;
+    //PWM1 = 0x03;
+    //PWM2 = 0x04;
+    //PWM3 = 0x05;
+    //PWM4 = 0x06;
+    //PWM5 = 0x07;
+    //PWM6 = 0x08;
+    //PWM7 = 0x09;
+    //PWM8 = 0x0A;
+    //PWM9 = 0x0B;
+    //PWM10 = 0x0C;
+    //PWM11 = 0x0D;
+    //PWM12 = 0x0E;
+    //PWM13 = 0x0F;
+    //PWM14 = 0x10;
+    //PWM15 = 0x11;
+    const GRPPWM: u8 = 0x12;
+    const GRPFREQ: u8 = 0x13;
+    const LEDOUT1: u8 = 0x14;
+    //LEDOUT2 = 0x15;
+    //LEDOUT3 = 0x16;
+    //LEDOUT4 = 0x17;
+    const SUBADR1: u8 = 0x18;
+    const SUBADR2: u8 = 0x19;
+    const SUBADR3: u8 = 0x1A;
+    const ALLCALLADR: u8 = 0x1B;
+
+    type Channels = Channels16;
+
+    fn recommended_config() -> Config {
+        Config::new()
+    }
+}
+
+/// PCA9624, 8 channels, open-drain only
+///
+/// There is no totem pole output stage, configs selecting `OutDrv::TotemPole`
+/// are rejected by `write_config_checked` and `bring_up`.
+#[derive(Copy, Clone, Debug)]
+pub struct PCA9624;
+
+impl Part for PCA9624 {
+    const MODE1: u8 = 0x00;
+    const MODE2: u8 = 0x01;
+    const PWM0: u8 = 0x02;
+    //PWM1 = 0x03;
+    //PWM2 = 0x04;
+    //PWM3 = 0x05;
+    //PWM4 = 0x06;
+    //PWM5 = 0x07;
+    //PWM6 = 0x08;
+    //PWM7 = 0x09;
+    const GRPPWM: u8 = 0x0A;
+    const GRPFREQ: u8 = 0x0B;
+    const LEDOUT1: u8 = 0x0C;
+    //LEDOUT2 = 0x0D;
+    const SUBADR1: u8 = 0x0E;
+    const SUBADR2: u8 = 0x0F;
+    const SUBADR3: u8 = 0x10;
+    const ALLCALLADR: u8 = 0x11;
+
+    type Channels = Channels8;
+
+    fn recommended_config() -> Config {
+        Config::new().out_drv(OutDrv::OpenDrain)
+    }
+
+    fn supports_config(conf: &Config) -> bool {
+        !conf.mode2.contains(Mode2::OutDrv)
+    }
+}
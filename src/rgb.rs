@@ -0,0 +1,156 @@
+//! RGB LEDs wired to three channels of a driver
+
+use crate::{i2c, Channels, Error, PCA963X};
+
+/// Convert a HSV color to RGB, all components in the range 0-255
+///
+/// The hue circle is split into six segments, red at 0, green at 85 and
+/// blue at 170.
+pub fn hsv_to_rgb(h: u8, s: u8, v: u8) -> (u8, u8, u8) {
+    let h6 = h as u32 * 6;
+    let (s, v) = (s as u32, v as u32);
+    let frac = h6 % 255;
+    let p = (v * (255 - s) / 255) as u8;
+    let q = (v * (255 - s * frac / 255) / 255) as u8;
+    let t = (v * (255 - s * (255 - frac) / 255) / 255) as u8;
+    let v = v as u8;
+    match (h6 / 255) % 6 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    }
+}
+
+/// RGB LED connected to three channels of a driver
+pub struct RgbLed<'a, D, C> {
+    pca: &'a mut D,
+    red: C,
+    green: C,
+    blue: C,
+}
+
+impl<'a, D, C> RgbLed<'a, D, C>
+where
+    C: Channels + Copy,
+{
+    /// Bind the `red`, `green` and `blue` channels of `pca`
+    pub fn new(pca: &'a mut D, red: C, green: C, blue: C) -> Self {
+        RgbLed {
+            pca,
+            red,
+            green,
+            blue,
+        }
+    }
+
+    /// Set color
+    ///
+    /// Written in a single auto-increment transaction when the three channels are
+    /// adjacent (in any order), otherwise one transaction per channel.
+    pub fn set_rgb<I2C, E>(&mut self, r: u8, g: u8, b: u8) -> Result<(), Error<E>>
+    where
+        D: PCA963X<I2C, E, Channels = C>,
+        I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+    {
+        let channels = [(self.red, r), (self.green, g), (self.blue, b)];
+        let (first, _) = *channels.iter().min_by_key(|(ch, _)| ch.get_offs()).unwrap();
+        let lo = first.get_offs();
+        let mut span = [None; 3];
+        for (ch, value) in &channels {
+            if let Some(slot) = span.get_mut(ch.get_offs().wrapping_sub(lo) as usize) {
+                *slot = Some(*value);
+            }
+        }
+        if let [Some(v0), Some(v1), Some(v2)] = span {
+            return self.pca.write_duty_range(first, &[v0, v1, v2]);
+        }
+        for (ch, value) in &channels {
+            self.pca.write_duty(*ch, *value)?;
+        }
+        Ok(())
+    }
+
+    /// Set color from hue, saturation and value, see [`hsv_to_rgb`]
+    pub fn set_hsv<I2C, E>(&mut self, h: u8, s: u8, v: u8) -> Result<(), Error<E>>
+    where
+        D: PCA963X<I2C, E, Channels = C>,
+        I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+    {
+        let (r, g, b) = hsv_to_rgb(h, s, v);
+        self.set_rgb(r, g, b)
+    }
+}
+
+#[cfg(test)]
+mod rgb_tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{Address, Channels4, Channels8, PCA9633, PCA9634};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+    use std::vec;
+
+    #[test]
+    fn test_hsv_red() {
+        assert_eq!(hsv_to_rgb(0, 255, 255), (255, 0, 0));
+    }
+
+    #[test]
+    fn test_hsv_green_blue() {
+        assert_eq!(hsv_to_rgb(85, 255, 255), (0, 255, 0));
+        assert_eq!(hsv_to_rgb(170, 255, 255), (0, 0, 255));
+        assert_eq!(hsv_to_rgb(170, 255, 100), (0, 0, 100));
+    }
+
+    #[test]
+    fn test_hsv_secondary() {
+        // Yellow, cyan and magenta fall between two hue steps
+        assert_eq!(hsv_to_rgb(43, 255, 255), (252, 255, 0));
+        assert_eq!(hsv_to_rgb(128, 255, 255), (0, 252, 255));
+        assert_eq!(hsv_to_rgb(213, 255, 255), (255, 0, 252));
+    }
+
+    #[test]
+    fn test_hsv_white() {
+        assert_eq!(hsv_to_rgb(0, 0, 255), (255, 255, 255));
+        assert_eq!(hsv_to_rgb(123, 0, 255), (255, 255, 255));
+        assert_eq!(hsv_to_rgb(123, 0, 100), (100, 100, 100));
+    }
+
+    #[test]
+    fn test_set_hsv() {
+        let expectations = [Transaction::write(0x62, vec![0xA2, 0, 0, 255])];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let mut led = RgbLed::new(&mut pca, Channels4::_3, Channels4::_2, Channels4::_1);
+        led.set_hsv(0, 255, 255).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_rgb_contiguous() {
+        let expectations = [Transaction::write(0x15, vec![0xA3, 20, 10, 30])];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9634::new(i2c.clone(), Address::Custom(0x15));
+        let mut led = RgbLed::new(&mut pca, Channels8::_3, Channels8::_2, Channels8::_4);
+        led.set_rgb(10, 20, 30).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_rgb_split() {
+        let expectations = [
+            Transaction::write(0x62, vec![0x02, 10]),
+            Transaction::write(0x62, vec![0x03, 20]),
+            Transaction::write(0x62, vec![0x05, 30]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let mut pca = PCA9633::new(i2c.clone(), Address::_8Pin);
+        let mut led = RgbLed::new(&mut pca, Channels4::_1, Channels4::_2, Channels4::_4);
+        led.set_rgb(10, 20, 30).unwrap();
+        i2c.done();
+    }
+}